@@ -0,0 +1,23 @@
+use bracoxide::parser::parse;
+use bracoxide::tokenizer::tokenize;
+use bracoxide::{expand, expand_in};
+use bumpalo::Bump;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn arena_benchmark(c: &mut Criterion) {
+    let items: Vec<String> = (0..10_000).map(|i| format!("item{i}")).collect();
+    let node = parse(&tokenize(&format!("prefix/{{{}}}.txt", items.join(","))).unwrap()).unwrap();
+    c.bench_function("large flat collection with expand", |b| {
+        b.iter(|| expand(black_box(&node)));
+    });
+    c.bench_function("large flat collection with expand_in", |b| {
+        let mut bump = Bump::new();
+        b.iter(|| {
+            black_box(expand_in(black_box(&node), &bump).unwrap());
+            bump.reset();
+        });
+    });
+}
+
+criterion_group!(benches, arena_benchmark);
+criterion_main!(benches);
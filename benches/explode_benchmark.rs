@@ -1,4 +1,6 @@
-use bracoxide::bracoxidize;
+use bracoxide::parser::parse;
+use bracoxide::tokenizer::tokenize;
+use bracoxide::{bracoxidize, expand, expand_boxed, expand_compact, Expander};
 use criterion::{criterion_group, criterion_main, Criterion, black_box};
 
 fn explode_benchmark(c: &mut Criterion) {
@@ -10,5 +12,76 @@ fn explode_benchmark(c: &mut Criterion) {
         });
 }
 
-criterion_group!(benches, explode_benchmark);
+fn long_text_benchmark(c: &mut Criterion) {
+    let content = format!("{}{{a,b}}", "x".repeat(1 << 20));
+    c.bench_function("long text benchmark", |b| {
+        b.iter(|| bracoxidize(black_box(&content)));
+    });
+}
+
+fn adjacent_groups_benchmark(c: &mut Criterion) {
+    let content = "{a}".repeat(50);
+    c.bench_function("adjacent groups benchmark", |b| {
+        b.iter(|| bracoxidize(black_box(&content)));
+    });
+}
+
+fn expander_benchmark(c: &mut Criterion) {
+    let node = parse(&tokenize("item{a,b,c}-{1..5}").unwrap()).unwrap();
+    c.bench_function("100k expansions without expander", |b| {
+        b.iter(|| {
+            for _ in 0..100_000 {
+                black_box(expand(black_box(&node)).unwrap());
+            }
+        });
+    });
+    c.bench_function("100k expansions with expander", |b| {
+        let mut expander = Expander::default();
+        b.iter(|| {
+            for _ in 0..100_000 {
+                black_box(expander.expand(black_box(&node)).unwrap());
+            }
+        });
+    });
+}
+
+fn flat_collection_benchmark(c: &mut Criterion) {
+    let items: Vec<String> = (0..100).map(|i| format!("item{i}")).collect();
+    let node = parse(&tokenize(&format!("prefix/{{{}}}.txt", items.join(","))).unwrap()).unwrap();
+    c.bench_function("flat collection benchmark", |b| {
+        b.iter(|| expand(black_box(&node)));
+    });
+}
+
+fn shared_prefix_benchmark(c: &mut Criterion) {
+    let prefix = "/very/long/shared/path/".repeat(20);
+    let node = parse(&tokenize(&format!("{prefix}{{a,b,c,d,e}}{{1..1000}}")).unwrap()).unwrap();
+    c.bench_function("shared prefix with expand", |b| {
+        b.iter(|| expand(black_box(&node)));
+    });
+    c.bench_function("shared prefix with expand_compact", |b| {
+        b.iter(|| expand_compact(black_box(&node)));
+    });
+}
+
+fn boxed_benchmark(c: &mut Criterion) {
+    let node = parse(&tokenize("{0..999}{0..999}").unwrap()).unwrap();
+    c.bench_function("million expansions with expand", |b| {
+        b.iter(|| expand(black_box(&node)));
+    });
+    c.bench_function("million expansions with expand_boxed", |b| {
+        b.iter(|| expand_boxed(black_box(&node)));
+    });
+}
+
+criterion_group!(
+    benches,
+    explode_benchmark,
+    long_text_benchmark,
+    adjacent_groups_benchmark,
+    expander_benchmark,
+    flat_collection_benchmark,
+    shared_prefix_benchmark,
+    boxed_benchmark
+);
 criterion_main!(benches);
\ No newline at end of file
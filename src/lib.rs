@@ -13,19 +13,20 @@
 //!
 //! ## Features
 //! - **Simple and Easy-to-Use**: With the bracoxide crate, expanding brace patterns in
-//! strings becomes a breeze. Just pass in your input string, and the crate will
-//! generate all possible combinations for you.
+//!   strings becomes a breeze. Just pass in your input string, and the crate will
+//!   generate all possible combinations for you.
 //!
 //! - **Flexible Brace Expansion**: The crate supports various brace expansion patterns,
-//! including numeric ranges ({0..9}), comma-separated options ({red,green,blue}),
-//! nested expansions ({a{b,c}d}, {x{1..3},y{4..6}}), and more.
+//!   including numeric ranges ({0..9}), character ranges ({a..z}),
+//!   alphabetic ranges ({aa..zz}), comma-separated options ({red,green,blue}),
+//!   nested expansions ({a{b,c}d}, {x{1..3},y{4..6}}), and more.
 //!
 //! - **Robust Error Handling**: The crate provides detailed error handling, allowing you
-//! to catch and handle any issues that may arise during the tokenization and expansion
-//! process.
+//!   to catch and handle any issues that may arise during the tokenization and expansion
+//!   process.
 //!
 //! - **Lightweight and Fast**: Designed to be efficient and performant, ensuring quick
-//! and reliable string expansion operations.
+//!   and reliable string expansion operations.
 //!
 //! ## Getting Started
 //!
@@ -61,6 +62,20 @@
 pub mod parser;
 pub mod tokenizer;
 
+/// The errors of every stage are available from the crate root, next to [OxidizationError]
+/// which wraps them, e.g.:
+///
+/// ```
+/// use bracoxide::{bracoxidize, OxidizationError, ParsingError};
+///
+/// assert_eq!(
+///     bracoxidize("a}b{c"),
+///     Err(OxidizationError::ParsingError(ParsingError::ExtraCBra(1)))
+/// );
+/// ```
+pub use parser::ParsingError;
+pub use tokenizer::TokenizationError;
+
 /// An error type representing the failure to expand a parsed node.
 ///
 /// This enum is used to indicate errors that can occur during the expansion of a parsed node.
@@ -69,11 +84,63 @@ pub mod tokenizer;
 /// # Variants
 ///
 /// - `NumConversionFailed(String)`: An error indicating that a number conversion failed during expansion.
-///                                 It contains a string representing the value that failed to be converted.
-#[derive(Debug, PartialEq)]
+///   It contains a string representing the value that failed to be converted.
+/// - `SampleCountTooSmall(usize)`: An error indicating that a range was asked for less than two
+///   evenly spread values.
+/// - `LimitExceeded { limit, span }`: An error indicating that the expansion would produce more
+///   combinations than allowed by [ExpandOptions::limit]. It contains the limit, and the span of
+///   the brace group which pushed the count over it.
+/// - `OutputTooLarge { bytes, max }`: An error indicating that the expansion produced more bytes
+///   than allowed by [ExpandOptions::max_output_bytes]. It contains the bytes produced when the
+///   expansion was aborted, and the maximum.
+/// - `NotANumericRange(span)`: An error indicating that [expand_numeric] was given anything but
+///   a numeric range. It contains the span of the node.
+/// - `RangeTooLarge { max, span }`: An error indicating that a single range produces more values
+///   than allowed by [ExpandOptions::max_range_len]. It contains the maximum, and the span of
+///   the range.
+/// - `GroupNotFound(index)`: An error indicating that [expand_group] was given the index of a
+///   brace group which doesn't exist, or [expand_selection] more choices than there are
+///   groups. It contains the index.
+/// - `ChoiceOutOfRange { group, index, len }`: An error indicating that [expand_selection] was
+///   given a choice past the values of its group. It contains the index of the group, the
+///   choice, and the count of values of the group.
+/// - `ChoiceMissing(group)`: An error indicating that [expand_selection] was given fewer
+///   choices than there are groups. It contains the index of the first group without a
+///   choice.
+///
+/// The enum is non-exhaustive, new variants may be added in a minor release, so a `match` on
+/// it needs a wildcard arm.
+#[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum ExpansionError {
     /// Error indicating that a number conversion failed during expansion.
     NumConversionFailed(String),
+    /// Error indicating that a range's sample count is less than 2, e.g. `{0..10:~1}`.
+    SampleCountTooSmall(usize),
+    /// Error indicating that the count of combinations exceeds the limit.
+    LimitExceeded {
+        limit: usize,
+        span: std::ops::Range<usize>,
+    },
+    /// Error indicating that the total size of the expansions exceeds the maximum.
+    OutputTooLarge { bytes: usize, max: usize },
+    /// Error indicating that the node isn't a single numeric range, e.g. `{a,b}`.
+    NotANumericRange(std::ops::Range<usize>),
+    /// Error indicating that a single range produces more values than the maximum.
+    RangeTooLarge {
+        max: usize,
+        span: std::ops::Range<usize>,
+    },
+    /// Error indicating that there is no brace group at the given index.
+    GroupNotFound(usize),
+    /// Error indicating that the choice for a brace group is past its values.
+    ChoiceOutOfRange {
+        group: usize,
+        index: usize,
+        len: usize,
+    },
+    /// Error indicating that there is no choice for the brace group at the given index.
+    ChoiceMissing(usize),
 }
 
 impl std::fmt::Display for ExpansionError {
@@ -82,6 +149,42 @@ impl std::fmt::Display for ExpansionError {
             ExpansionError::NumConversionFailed(content) => {
                 write!(f, "Number conversion of \"{}\" failed.", content)
             }
+            ExpansionError::SampleCountTooSmall(count) => {
+                write!(f, "Sample count {} is less than 2.", count)
+            }
+            ExpansionError::LimitExceeded { limit, span } => write!(
+                f,
+                "Brace group at {}..{} exceeds the limit of {} combinations.",
+                span.start, span.end, limit
+            ),
+            ExpansionError::OutputTooLarge { bytes, max } => write!(
+                f,
+                "Expansion output of at least {} bytes exceeds the maximum of {} bytes.",
+                bytes, max
+            ),
+            ExpansionError::RangeTooLarge { max, span } => write!(
+                f,
+                "Range at {}..{} produces more than {} values.",
+                span.start, span.end, max
+            ),
+            ExpansionError::NotANumericRange(span) => {
+                write!(
+                    f,
+                    "Node at {}..{} is not a numeric range.",
+                    span.start, span.end
+                )
+            }
+            ExpansionError::GroupNotFound(index) => {
+                write!(f, "There is no brace group at index {}.", index)
+            }
+            ExpansionError::ChoiceOutOfRange { group, index, len } => write!(
+                f,
+                "Choice {} is out of range for brace group {} of {} values.",
+                index, group, len
+            ),
+            ExpansionError::ChoiceMissing(group) => {
+                write!(f, "There is no choice for brace group {}.", group)
+            }
         }
     }
 }
@@ -105,7 +208,7 @@ impl std::error::Error for ExpansionError {}
 /// use bracoxide::parser::Node;
 /// use bracoxide::{expand, ExpansionError};
 ///
-/// let node = Node::Text { message: "Hello".to_owned().into(), start: 0 };
+/// let node = Node::Text { message: "Hello".to_owned().into(), start: 0, end: 5 };
 /// let expanded = expand(&node);
 /// assert_eq!(expanded, Ok(vec!["Hello".to_owned()]));
 /// ```
@@ -123,298 +226,5264 @@ impl std::error::Error for ExpansionError {}
 ///
 /// This function operates on valid parsed nodes and does not use unsafe code internally.
 pub fn expand(node: &crate::parser::Node) -> Result<Vec<String>, ExpansionError> {
-    match node {
-        parser::Node::Text { message, start: _ } => Ok(vec![message.as_ref().to_owned()]),
-        parser::Node::BraceExpansion {
-            prefix,
-            inside,
-            postfix,
-            start: _,
-            end: _,
-        } => {
-            let mut inner = vec![];
-            let prefixs: Vec<String> = if let Some(prefix) = prefix {
-                expand(prefix)?
-            } else {
-                vec!["".to_owned()]
-            };
-            let insides: Vec<String> = if let Some(inside) = inside {
-                expand(inside)?
-            } else {
-                vec!["".to_owned()]
-            };
-            let postfixs: Vec<String> = if let Some(postfix) = postfix {
-                expand(postfix)?
-            } else {
-                vec!["".to_owned()]
-            };
-            for prefix in &prefixs {
-                for inside in &insides {
-                    for postfix in &postfixs {
-                        inner.push(format!("{}{}{}", prefix, inside, postfix));
-                    }
-                }
-            }
-            Ok(inner)
-        }
-        parser::Node::Collection {
-            items,
-            start: _,
-            end: _,
-        } => {
-            let mut inner = vec![];
-            for item in items {
-                let expansions = expand(item)?;
-                inner.extend(expansions);
-            }
-            Ok(inner)
-        }
-        parser::Node::Range {
-            from,
-            to,
-            start: _,
-            end: _,
-        } => {
-            let from = if let Ok(from) = from.parse::<usize>() {
-                from
-            } else {
-                return Err(ExpansionError::NumConversionFailed(from.to_string()));
-            };
+    expand_with(node, &ExpandOptions::default())
+}
 
-            let to = if let Ok(to) = to.parse::<usize>() {
-                to
-            } else {
-                return Err(ExpansionError::NumConversionFailed(to.to_string()));
-            };
-            let range = from..=to;
-            let mut inner = vec![];
-            for i in range {
-                inner.push(i.to_string());
+/// Determines which characters a character range (e.g. `{A..z}`) produces.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum CharRangeMode {
+    /// Every valid character between the limits, e.g. `{Y..b}` yields
+    /// `Y`, `Z`, `[`, `\`, `]`, `^`, `_`, `` ` ``, `a`, `b`.
+    #[default]
+    Raw,
+    /// Only alphanumeric characters between the limits, e.g. `{Y..b}` yields
+    /// `Y`, `Z`, `a`, `b`.
+    AlnumOnly,
+}
+
+/// Options controlling how a parsed node is expanded by [expand_with].
+///
+/// The default options produce the same output as [expand].
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::{bracoxidize_with, CharRangeMode, ExpandOptions};
+///
+/// let options = ExpandOptions::default().char_range_mode(CharRangeMode::AlnumOnly);
+/// let expanded = bracoxidize_with("{Y..b}", &options).unwrap();
+/// assert_eq!(expanded, vec!["Y", "Z", "a", "b"]);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ExpandOptions {
+    char_range_mode: CharRangeMode,
+    limit: Option<usize>,
+    tokenizer: tokenizer::TokenizerOptions,
+    allow_comments: bool,
+    max_output_bytes: Option<usize>,
+    max_range_len: Option<usize>,
+    passthrough_blank: bool,
+    range_format: Option<RangeFormat>,
+    number_format: NumberFormat,
+}
+
+/// Determines how the values of numeric ranges (e.g. `{1000..1002}`) are written, see
+/// [ExpandOptions::number_format].
+///
+/// The default format writes the plain decimal value, like bash.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::{bracoxidize_with, ExpandOptions, NumberFormat};
+///
+/// let options = ExpandOptions::default().number_format(NumberFormat::default().grouping('.'));
+/// let expanded = bracoxidize_with("{999..1001}", &options).unwrap();
+/// assert_eq!(expanded, vec!["999", "1.000", "1.001"]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct NumberFormat {
+    separator: Option<char>,
+}
+
+impl NumberFormat {
+    /// Separates the digits in groups of three with `separator`, counting from the right, e.g.
+    /// `1,000,000` with `,`. Not grouped by default.
+    pub fn grouping(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Writes the given value zero-padded to `width`, sign included, then groups its digits.
+    fn format(&self, value: i128, width: usize) -> String {
+        let padded = format!("{:0width$}", value);
+        let Some(separator) = self.separator else {
+            return padded;
+        };
+        let (sign, digits) = padded.split_at(usize::from(value < 0));
+        let mut formatted = sign.to_owned();
+        for (n, c) in digits.chars().enumerate() {
+            if n > 0 && (digits.len() - n) % 3 == 0 {
+                formatted.push(separator);
             }
-            Ok(inner)
+            formatted.push(c);
         }
+        formatted
     }
 }
 
-/// Same functionality as [bracoxidize] but with explosive materials. This crates' all
-/// Error types (except the [OxidizationError]) implements [std::error::Error] trait. Why not get all the benefits from it?
-pub fn explode(content: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let tokens = tokenizer::tokenize(content)?;
-    let ast = parser::parse(&tokens)?;
-    let expansions = expand(&ast)?;
-    Ok(expansions)
+/// Renders the values of numeric ranges, see [ExpandOptions::range_format].
+#[derive(Clone)]
+struct RangeFormat(std::sync::Arc<dyn Fn(i128) -> String + Send + Sync>);
+
+impl std::fmt::Debug for RangeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RangeFormat(..)")
+    }
 }
 
-/// Errors that can occur during the Brace Expansion process.
-#[derive(Debug, PartialEq)]
-pub enum OxidizationError {
-    TokenizationError(tokenizer::TokenizationError),
-    ParsingError(parser::ParsingError),
-    ExpansionError(ExpansionError),
+impl PartialEq for RangeFormat {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
-/// Bracoxidize the provided content by tokenizing, parsing, and expanding brace patterns.
+impl ExpandOptions {
+    /// Sets the options used to tokenize the content in [bracoxidize_with].
+    pub fn tokenizer(mut self, options: tokenizer::TokenizerOptions) -> Self {
+        self.tokenizer = options;
+        self
+    }
+
+    /// Sets which characters a character range produces. Defaults to [CharRangeMode::Raw].
+    pub fn char_range_mode(mut self, mode: CharRangeMode) -> Self {
+        self.char_range_mode = mode;
+        self
+    }
+
+    /// Sets the maximum count of combinations the expansion may produce. The count is
+    /// checked before expanding, and [ExpansionError::LimitExceeded] is returned if the
+    /// pattern exceeds it. Unlimited by default.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the maximum total size, in bytes, of the expansions. Unlike [ExpandOptions::limit]
+    /// it is checked while expanding, and [ExpansionError::OutputTooLarge] is returned as soon
+    /// as the expansions grow past it. This guards against few but enormous combinations.
+    /// Unlimited by default.
+    pub fn max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = Some(max);
+        self
+    }
+
+    /// Sets the maximum count of values a single range, e.g. `{0..9999}`, may produce. Unlike
+    /// [ExpandOptions::limit] it applies to every range on its own, and
+    /// [ExpansionError::RangeTooLarge] is returned if one exceeds it, however small the total
+    /// count is. This catches typos like a stray digit in a limit. Unlimited by default.
+    pub fn max_range_len(mut self, max: usize) -> Self {
+        self.max_range_len = Some(max);
+        self
+    }
+
+    /// Makes [bracoxidize_with] return blank content, which is empty or whitespace only, as
+    /// is, e.g. `"  "` expands to `["  "]`. Disabled by default, blank content fails with
+    /// [TokenizationError::EmptyContent] if it's empty, and [TokenizationError::NoBraces]
+    /// otherwise.
+    pub fn passthrough_blank(mut self, passthrough_blank: bool) -> Self {
+        self.passthrough_blank = passthrough_blank;
+        self
+    }
+
+    /// Enables comments in [bracoxidize_with]. Disabled by default, `#` is ordinary text then.
+    ///
+    /// When enabled, a `#` starts a comment running to the end of the line, which is stripped
+    /// before tokenizing, so multi-line patterns can be annotated. An escaped `#` (`\#`) is
+    /// still literal.
+    pub fn allow_comments(mut self, allow_comments: bool) -> Self {
+        self.allow_comments = allow_comments;
+        self
+    }
+
+    /// Sets how the values of numeric ranges are rendered, e.g. with thousands separators.
+    /// Only the values generated by ranges like `{1..3}` are passed to `format`, text and
+    /// the items of collections are left untouched. Defaults to the decimal value, zero-padded
+    /// like bash if a limit is written with leading zeros, e.g. `{01..10}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::{bracoxidize_with, ExpandOptions};
+    ///
+    /// let options = ExpandOptions::default().range_format(|i| format!("{:02}", i));
+    /// let expanded = bracoxidize_with("item_{1..3}", &options).unwrap();
+    /// assert_eq!(expanded, vec!["item_01", "item_02", "item_03"]);
+    /// ```
+    pub fn range_format<F>(mut self, format: F) -> Self
+    where
+        F: Fn(i128) -> String + Send + Sync + 'static,
+    {
+        self.range_format = Some(RangeFormat(std::sync::Arc::new(format)));
+        self
+    }
+
+    /// Sets how the values of numeric ranges are written, e.g. with thousands separators, see
+    /// [NumberFormat]. Text and the items of collections are left untouched. Defaults to
+    /// [NumberFormat::default].
+    ///
+    /// A value is zero-padded first, to the digit count of a limit written with leading zeros,
+    /// and its digits are grouped then, e.g. `{0999..1000}` gives `0,999` and `1,000`. A
+    /// [range_format](ExpandOptions::range_format) takes precedence over it.
+    pub fn number_format(mut self, format: NumberFormat) -> Self {
+        self.number_format = format;
+        self
+    }
+}
+
+/// Expands the given parsed node like [expand], using the provided [ExpandOptions].
 ///
 /// # Arguments
 ///
-/// * `content` - The input string to be processed.
+/// * `node` - The parsed node to be expanded.
+/// * `options` - The options controlling the expansion.
 ///
-/// # Returns
+/// # Errors
 ///
-/// Returns a `Result` containing the expanded brace patterns as `Vec<String>`,
-/// or an `OxidizationError` if an error occurs during the process.
+/// Returns an `ExpansionError` if the expansion fails, same as [expand].
+pub fn expand_with(
+    node: &crate::parser::Node,
+    options: &ExpandOptions,
+) -> Result<Vec<String>, ExpansionError> {
+    if let Some(limit) = options.limit {
+        count_limited(node, options, limit)?;
+    }
+    expand_node(node, options)
+}
+
+/// Expands the given parsed node, which must be a single numeric range like `{1..5}`, into
+/// its values as integers, without formatting them as strings.
 ///
 /// # Examples
 ///
-/// ```rust
-/// use bracoxide::{bracoxidize, OxidizationError};
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_numeric;
 ///
-/// fn main() {
-///     let content = "foo{1..3}bar";
-///     match bracoxidize(content) {
-///         Ok(expanded) => {
-///             println!("Expanded patterns: {:?}", expanded);
-///         }
-///         Err(error) => {
-///             eprintln!("Error occurred: {:?}", error);
-///         }
-///     }
-/// }
+/// let node = parse(&tokenize("{10..1..3}").unwrap()).unwrap();
+/// assert_eq!(expand_numeric(&node), Ok(vec![10, 7, 4, 1]));
 /// ```
-pub fn bracoxidize(content: &str) -> Result<Vec<String>, OxidizationError> {
-    // Tokenize the input string
-    let tokens = match tokenizer::tokenize(content) {
-        Ok(tokens) => tokens,
-        Err(error) => return Err(OxidizationError::TokenizationError(error)),
+///
+/// # Errors
+///
+/// Returns [ExpansionError::NotANumericRange] if the node is anything but a numeric range,
+/// e.g. `{a,b}` or `x{1..5}`, or [ExpansionError::NumConversionFailed] if a value doesn't fit
+/// in an `i64`.
+pub fn expand_numeric(node: &crate::parser::Node) -> Result<Vec<i64>, ExpansionError> {
+    let [parser::Node::Range {
+        from,
+        to,
+        inclusive,
+        sample_count,
+        step,
+        ..
+    }] = chain_parts(node)[..]
+    else {
+        return Err(ExpansionError::NotANumericRange(node.span()));
     };
+    range_values(
+        from,
+        to,
+        *inclusive,
+        sample_count.as_deref(),
+        step.as_deref(),
+    )?
+    .into_iter()
+    .map(|value| {
+        i64::try_from(value).map_err(|_| ExpansionError::NumConversionFailed(value.to_string()))
+    })
+    .collect()
+}
 
-    // Parse the tokens into an abstract syntax tree
-    let ast = match parser::parse(&tokens) {
-        Ok(ast) => ast,
-        Err(error) => return Err(OxidizationError::ParsingError(error)),
-    };
+/// Expands parsed nodes like [expand_with], reusing its buffers between calls.
+///
+/// Useful when expanding lots of patterns one after another: the strings holding the
+/// expansions of the previous call are cleared and filled again, rather than allocated anew.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::Expander;
+///
+/// let mut expander = Expander::default();
+/// let node = parse(&tokenize("{a,b}").unwrap()).unwrap();
+/// assert_eq!(expander.expand(&node).unwrap(), ["a", "b"]);
+/// let node = parse(&tokenize("x{1..3}").unwrap()).unwrap();
+/// assert_eq!(expander.expand(&node).unwrap(), ["x1", "x2", "x3"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct Expander {
+    options: ExpandOptions,
+    // only the first `len` expansions belong to the last call, the rest are kept for reuse.
+    expansions: Vec<String>,
+    len: usize,
+    // the parts of the last node, kept to reuse their allocations.
+    combinations: Combinations<String>,
+}
 
-    // Expand the brace patterns in the AST
-    let expanded = match expand(&ast) {
-        Ok(expanded) => expanded,
-        Err(error) => return Err(OxidizationError::ExpansionError(error)),
-    };
+impl Expander {
+    /// Creates an expander expanding with the provided [ExpandOptions].
+    pub fn new(options: ExpandOptions) -> Self {
+        Expander {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Expands the given parsed node, producing the same expansions as [expand_with] with the
+    /// options of the expander. The returned slice is valid until the next call.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [expand_with].
+    pub fn expand(&mut self, node: &parser::Node) -> Result<&[String], ExpansionError> {
+        if let Some(limit) = self.options.limit {
+            count_limited(node, &self.options, limit)?;
+        }
+        self.len = 0;
+        self.combinations.expand_again(node, &self.options)?;
+        let mut bytes = 0_usize;
+        loop {
+            if self.len == self.expansions.len() {
+                self.expansions.push(String::new());
+            }
+            let expansion = &mut self.expansions[self.len];
+            if !self.combinations.next_into(expansion) {
+                break;
+            }
+            if let Some(max) = self.options.max_output_bytes {
+                bytes = bytes.saturating_add(expansion.len());
+                if bytes > max {
+                    return Err(ExpansionError::OutputTooLarge { bytes, max });
+                }
+            }
+            self.len += 1;
+        }
+        Ok(&self.expansions[..self.len])
+    }
+}
+
+/// Advances `indices`, the index of the value used for each of the `parts`, to the next
+/// combination like an odometer, the last part changing fastest. Returns `false` once every
+/// combination is visited, the indices are all back to 0 then.
+fn advance<T>(parts: &[Vec<T>], indices: &mut [usize]) -> bool {
+    for (part, i) in parts.iter().zip(indices).rev() {
+        *i += 1;
+        if *i < part.len() {
+            return true;
+        }
+        *i = 0;
+    }
+    false
+}
+
+/// The combinations of the values of the parts of a postfix chain, visited one after another
+/// like an odometer, see [advance].
+///
+/// The values of each part are kept apart, a combination is only built when visited, e.g.
+/// into a single reused buffer with [Combinations::next_into].
+#[derive(Debug, Default)]
+struct Combinations<T> {
+    parts: Vec<Vec<T>>,
+    // index of the value used for each part, the last part changing fastest.
+    indices: Vec<usize>,
+    // whether the current combination was already visited.
+    visited: bool,
+    // whether every combination was visited, or there is none.
+    done: bool,
+}
+
+impl<T> Combinations<T> {
+    /// Starts before the first combination of the values of `parts`.
+    fn new(parts: Vec<Vec<T>>) -> Self {
+        let mut combinations = Combinations {
+            parts,
+            indices: vec![],
+            visited: false,
+            done: false,
+        };
+        combinations.restart();
+        combinations
+    }
+
+    /// Starts over before the first combination, e.g. after the parts were replaced.
+    fn restart(&mut self) {
+        self.indices.clear();
+        self.indices.resize(self.parts.len(), 0);
+        self.visited = false;
+        self.done = self.parts.iter().any(Vec::is_empty);
+    }
+
+    /// Moves right before the combination at `cursor`, in the order they are visited. Returns
+    /// `false` if the cursor is past the last combination, there's none left then.
+    fn seek(&mut self, cursor: usize) -> bool {
+        self.done = self.done || !seek(&self.parts, cursor, &mut self.indices);
+        self.visited = false;
+        !self.done
+    }
+
+    /// Moves to the next combination, the first one on the first call. Returns `false` once
+    /// every combination was visited.
+    fn advance(&mut self) -> bool {
+        if self.visited && !self.done {
+            self.done = !advance(&self.parts, &mut self.indices);
+        }
+        self.visited = true;
+        !self.done
+    }
+
+    /// Returns the values of the current combination, one per part.
+    fn values(&self) -> impl Iterator<Item = &T> {
+        self.parts
+            .iter()
+            .zip(&self.indices)
+            .map(|(part, &i)| &part[i])
+    }
 
+    /// Returns the count of combinations, or `None` if it doesn't fit in a `usize`.
+    fn len(&self) -> Option<usize> {
+        self.parts
+            .iter()
+            .try_fold(1_usize, |total, part| total.checked_mul(part.len()))
+    }
+}
+
+impl Combinations<String> {
+    /// Expands the parts of the postfix chain of `node` on their own, and starts before their
+    /// first combination.
+    fn expand(node: &parser::Node, options: &ExpandOptions) -> Result<Self, ExpansionError> {
+        let mut combinations = Combinations::default();
+        combinations.expand_again(node, options)?;
+        Ok(combinations)
+    }
+
+    /// Replaces the parts like [Combinations::expand], reusing the allocations of the previous
+    /// parts.
+    fn expand_again(
+        &mut self,
+        node: &parser::Node,
+        options: &ExpandOptions,
+    ) -> Result<(), ExpansionError> {
+        self.parts.clear();
+        for (part, _) in chain_groups(node) {
+            self.parts.push(expand_node(part, options)?);
+        }
+        self.restart();
+        Ok(())
+    }
+}
+
+impl<T: AsRef<str>> Combinations<T> {
+    /// Moves to the next combination like [Combinations::advance], and builds it into
+    /// `buffer`, replacing its content.
+    fn next_into(&mut self, buffer: &mut String) -> bool {
+        if !self.advance() {
+            return false;
+        }
+        buffer.clear();
+        for value in self.values() {
+            buffer.push_str(value.as_ref());
+        }
+        true
+    }
+}
+
+/// Expands the given parsed node like [expand], but only keeps the expansions for which `f`
+/// returns `true`.
+///
+/// Only the matching combinations are stored, so it's cheaper than expanding everything and
+/// retaining the matches.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_filter;
+///
+/// let node = parse(&tokenize("file{1..3}.{txt,md}").unwrap()).unwrap();
+/// let expanded = expand_filter(&node, |file| file.ends_with(".md")).unwrap();
+/// assert_eq!(expanded, vec!["file1.md", "file2.md", "file3.md"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_filter<F: Fn(&str) -> bool>(
+    node: &crate::parser::Node,
+    f: F,
+) -> Result<Vec<String>, ExpansionError> {
+    let mut combinations = Combinations::expand(node, &ExpandOptions::default())?;
+    let mut expanded = vec![];
+    let mut buffer = String::new();
+    while combinations.next_into(&mut buffer) {
+        if f(&buffer) {
+            expanded.push(buffer.clone());
+        }
+    }
     Ok(expanded)
 }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+/// Expands the given parsed node like [expand], but only keeps the first expansion for each
+/// key `f` derives from it, e.g. one expansion per case-insensitive spelling.
+///
+/// The kept expansions are in the order [expand] produces them. Only the combinations with a
+/// new key are stored.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_unique_by;
+///
+/// let node = parse(&tokenize("{A,a,B}.txt").unwrap()).unwrap();
+/// let expanded = expand_unique_by(&node, str::to_lowercase).unwrap();
+/// assert_eq!(expanded, vec!["A.txt", "B.txt"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_unique_by<K, F>(
+    node: &crate::parser::Node,
+    f: F,
+) -> Result<Vec<String>, ExpansionError>
+where
+    K: Eq + std::hash::Hash,
+    F: Fn(&str) -> K,
+{
+    let mut combinations = Combinations::expand(node, &ExpandOptions::default())?;
+    let mut seen = std::collections::HashSet::new();
+    let mut expanded = vec![];
+    let mut buffer = String::new();
+    while combinations.next_into(&mut buffer) {
+        if seen.insert(f(&buffer)) {
+            expanded.push(buffer.clone());
+        }
+    }
+    Ok(expanded)
+}
 
-    use super::parser::Node;
-    use super::*;
-    #[test]
-    fn test_expand_complex() {
-        assert_eq!(
-            expand(&Node::BraceExpansion {
-                prefix: Some(Box::new(Node::Text {
-                    message: Arc::new("A".into()),
-                    start: 0
-                })),
-                inside: Some(Box::new(Node::Collection {
-                    items: vec![
-                        Node::Text {
-                            message: Arc::new("B".into()),
-                            start: 2
-                        },
-                        Node::BraceExpansion {
-                            prefix: Some(Box::new(Node::Text {
-                                message: Arc::new("C".into()),
-                                start: 4
-                            })),
-                            inside: Some(Box::new(Node::Collection {
-                                items: vec![
-                                    Node::Text {
-                                        message: Arc::new("D".into()),
-                                        start: 6
-                                    },
-                                    Node::Text {
-                                        message: Arc::new("E".into()),
-                                        start: 8
-                                    },
-                                ],
-                                start: 5,
-                                end: 9
-                            })),
-                            postfix: Some(Box::new(Node::Text {
-                                message: Arc::new("F".into()),
-                                start: 10
-                            })),
-                            start: 4,
-                            end: 10,
-                        },
-                        Node::Text {
-                            message: Arc::new("G".into()),
-                            start: 12
+/// Expands the given parsed node like [expand], but into boxed strings, which have no spare
+/// capacity, and are a word smaller than a [String] each. This saves memory when storing
+/// millions of short expansions.
+///
+/// Every combination is copied into a box of its exact length.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_boxed;
+///
+/// let node = parse(&tokenize("{a,b}{1..2}").unwrap()).unwrap();
+/// let expanded = expand_boxed(&node).unwrap();
+/// assert_eq!(expanded, vec!["a1".into(), "a2".into(), "b1".into(), "b2".into()]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_boxed(node: &crate::parser::Node) -> Result<Vec<Box<str>>, ExpansionError> {
+    let mut combinations = Combinations::expand(node, &ExpandOptions::default())?;
+    // the count is only a hint, a count that overflows couldn't be stored anyway.
+    let mut expanded = Vec::with_capacity(combinations.len().unwrap_or(0));
+    let mut buffer = String::new();
+    while combinations.next_into(&mut buffer) {
+        expanded.push(Box::from(buffer.as_str()));
+    }
+    Ok(expanded)
+}
+
+/// Expands the given parsed node like [expand], but a group which fails to expand, e.g. a
+/// range with a limit too large, only fails the combinations it's part of, rather than the
+/// whole expansion.
+///
+/// Each combination is either its expansion, or the error of the first group in it which
+/// failed, in the same order as [expand]. A failed group counts as a single value, so a
+/// collection with one bad range among many items keeps the expansions of the other items.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_lossy;
+///
+/// let node = parse(&tokenize("{a,{1..99999999999999999999}}").unwrap()).unwrap();
+/// let (ok, failed): (Vec<_>, Vec<_>) = expand_lossy(&node).into_iter().partition(Result::is_ok);
+/// assert_eq!(ok, vec![Ok("a".to_owned())]);
+/// assert_eq!(failed.len(), 1);
+/// ```
+pub fn expand_lossy(node: &crate::parser::Node) -> Vec<Result<String, ExpansionError>> {
+    match node {
+        parser::Node::BraceExpansion { .. } => {
+            let parts = chain_groups(node).map(|(part, _)| expand_lossy(part));
+            let mut combinations = Combinations::new(parts.collect());
+            let mut expanded = vec![];
+            while combinations.advance() {
+                let values = combinations.values().map(Result::as_deref);
+                expanded.push(values.collect::<Result<String, _>>().map_err(Clone::clone));
+            }
+            expanded
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            items.iter().flat_map(expand_lossy).collect()
+        }
+        _ => match expand_node(node, &ExpandOptions::default()) {
+            Ok(values) => values.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        },
+    }
+}
+
+/// Expands the given parsed node like [expand], but allocates the expansions in the given
+/// arena, so a whole batch of expansions is freed at once with the arena. Requires the
+/// `bumpalo` feature.
+///
+/// Every combination is copied into the arena, so no expansion is a heap allocation of its
+/// own.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_in;
+/// use bumpalo::Bump;
+///
+/// let bump = Bump::new();
+/// let node = parse(&tokenize("file{1..3}.txt").unwrap()).unwrap();
+/// let expanded = expand_in(&node, &bump).unwrap();
+/// assert_eq!(expanded, vec!["file1.txt", "file2.txt", "file3.txt"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+#[cfg(feature = "bumpalo")]
+pub fn expand_in<'a>(
+    node: &crate::parser::Node,
+    bump: &'a bumpalo::Bump,
+) -> Result<Vec<&'a str>, ExpansionError> {
+    fn text(node: &parser::Node) -> Option<std::borrow::Cow<'_, str>> {
+        match node {
+            parser::Node::Text { message, .. } => Some(std::borrow::Cow::Borrowed(message)),
+            _ => None,
+        }
+    }
+    let options = ExpandOptions::default();
+    let mut parts = vec![];
+    for (part, _) in chain_groups(node) {
+        // texts are borrowed from the node, they are copied into the arena anyway.
+        let values = match part {
+            parser::Node::Collection { items, .. } => items.iter().map(text).collect(),
+            part => text(part).map(|text| vec![text]),
+        };
+        parts.push(match values {
+            Some(values) => values,
+            None => expand_node(part, &options)?
+                .into_iter()
+                .map(std::borrow::Cow::Owned)
+                .collect(),
+        });
+    }
+    let mut combinations = Combinations::new(parts);
+    let mut expanded = vec![];
+    let mut buffer = String::new();
+    while combinations.next_into(&mut buffer) {
+        expanded.push(&*bump.alloc_str(&buffer));
+    }
+    Ok(expanded)
+}
+
+/// An expansion returned by [expand_compact], made of the literal text leading the pattern,
+/// shared by all the expansions, and the rest of the expansion.
+///
+/// The full expansion is only built on demand, through its [Display](std::fmt::Display),
+/// e.g. with `to_string()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CompactExpansion {
+    prefix: std::sync::Arc<str>,
+    suffix: String,
+}
+
+impl CompactExpansion {
+    /// Returns the literal text leading the expansion, shared by all the expansions of the
+    /// pattern.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the rest of the expansion, following the [prefix](CompactExpansion::prefix).
+    pub fn suffix(&self) -> &str {
+        &self.suffix
+    }
+}
+
+impl std::fmt::Display for CompactExpansion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.prefix, self.suffix)
+    }
+}
+
+/// Expands the given parsed node like [expand], but the literal text leading the pattern is
+/// stored once and shared by all the expansions, rather than copied into each of them.
+///
+/// This saves memory for patterns with a long literal prefix, e.g. a path, followed by many
+/// combinations. The expansions are in the same order as [expand] returns them.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_compact;
+///
+/// let node = parse(&tokenize("/home/user/{a,b}.txt").unwrap()).unwrap();
+/// let expanded = expand_compact(&node).unwrap();
+/// assert_eq!(expanded[0].prefix(), "/home/user/");
+/// assert_eq!(expanded[0].suffix(), "a.txt");
+/// assert_eq!(expanded[1].to_string(), "/home/user/b.txt");
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_compact(node: &crate::parser::Node) -> Result<Vec<CompactExpansion>, ExpansionError> {
+    let parts = chain_parts(node);
+    let mut prefix = String::new();
+    let mut rest = parts.len();
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            parser::Node::Text { message, .. } => prefix.push_str(message),
+            _ => {
+                rest = i;
+                break;
+            }
+        }
+    }
+    let prefix: std::sync::Arc<str> = prefix.into();
+    let rest = chain(parts[rest..].iter().map(|&part| part.clone()).collect());
+    Ok(expand(&rest)?
+        .into_iter()
+        .map(|suffix| CompactExpansion {
+            prefix: prefix.clone(),
+            suffix,
+        })
+        .collect())
+}
+
+/// A parsed pattern which caches the count of its combinations once computed, so repeated
+/// [Pattern::count] calls and limit checks don't walk the tree again.
+///
+/// # Examples
+///
+/// A pattern can be parsed from a string and expanded in one line:
+///
+/// ```
+/// use bracoxide::Pattern;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let expanded = Pattern::try_from("{a,b}")?.expand()?;
+/// assert_eq!(expanded, vec!["a", "b"]);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```
+/// use bracoxide::{ExpandOptions, Pattern};
+///
+/// let pattern = Pattern::parse("{a,b}{1..3}").unwrap();
+/// assert_eq!(pattern.count(), Ok(6));
+/// let options = ExpandOptions::default().limit(10);
+/// assert_eq!(pattern.expand_with(&options).unwrap().len(), 6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    node: parser::Node,
+    count: std::cell::OnceCell<usize>,
+}
+
+impl Pattern {
+    /// Creates a pattern from the given parsed node.
+    pub fn new(node: parser::Node) -> Self {
+        Pattern {
+            node,
+            count: std::cell::OnceCell::new(),
+        }
+    }
+
+    /// Tokenizes and parses the provided content into a pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [OxidizationError] if the content can't be tokenized or parsed.
+    pub fn parse(content: &str) -> Result<Self, OxidizationError> {
+        let tokens = tokenizer::tokenize(content)?;
+        Ok(Pattern::new(parser::parse(&tokens)?))
+    }
+
+    /// Returns the parsed node of the pattern.
+    pub fn node(&self) -> &parser::Node {
+        &self.node
+    }
+
+    /// Counts the combinations of the pattern like [count]. The count is computed by the first
+    /// successful call only, later calls return the cached count.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [count], which aren't cached.
+    pub fn count(&self) -> Result<usize, ExpansionError> {
+        if let Some(count) = self.count.get() {
+            return Ok(*count);
+        }
+        let count = count(&self.node)?;
+        Ok(*self.count.get_or_init(|| count))
+    }
+
+    /// Expands the pattern like [expand].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [expand].
+    pub fn expand(&self) -> Result<Vec<String>, ExpansionError> {
+        self.expand_with(&ExpandOptions::default())
+    }
+
+    /// Expands the pattern like [expand_with]. The limit of the options is checked against the
+    /// cached count, unless the [CharRangeMode] changes which values char ranges produce.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [expand_with].
+    pub fn expand_with(&self, options: &ExpandOptions) -> Result<Vec<String>, ExpansionError> {
+        if let Some(limit) = options.limit {
+            let within = options.char_range_mode == CharRangeMode::default()
+                && matches!(self.count(), Ok(count) if count <= limit);
+            if !within {
+                // Count again to report the group which exceeds the limit.
+                count_limited(&self.node, options, limit)?;
+            }
+        }
+        expand_node(&self.node, options)
+    }
+}
+
+impl From<parser::Node> for Pattern {
+    fn from(node: parser::Node) -> Self {
+        Pattern::new(node)
+    }
+}
+
+/// Tokenizes and parses the content like [Pattern::parse].
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::{OxidizationError, Pattern};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// assert_eq!(Pattern::try_from("{a,b}")?.expand()?, vec!["a", "b"]);
+/// assert!(matches!(
+///     Pattern::try_from("a"),
+///     Err(OxidizationError::TokenizationError(_))
+/// ));
+/// # Ok(())
+/// # }
+/// ```
+impl TryFrom<&str> for Pattern {
+    type Error = OxidizationError;
+
+    fn try_from(content: &str) -> Result<Self, Self::Error> {
+        Pattern::parse(content)
+    }
+}
+
+/// Tokenizes and parses the content like [Pattern::parse], so a pattern can be parsed with
+/// [str::parse].
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::Pattern;
+///
+/// let pattern: Pattern = "img{1..3}.png".parse().unwrap();
+/// assert_eq!(pattern.count(), Ok(3));
+/// ```
+impl std::str::FromStr for Pattern {
+    type Err = OxidizationError;
+
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(content)
+    }
+}
+
+/// Expands the `cursor..cursor + len` slice of the expansions of the given parsed node, in
+/// the same order as [expand], without producing the combinations before the cursor.
+///
+/// The cursor is decoded into the value used for each brace group (a mixed-radix number, the
+/// last group being the least significant digit), so resuming deep into a pattern with
+/// billions of combinations is cheap. The values of each group are still expanded on their
+/// own. The slice is cut short at the end of the expansions, and empty past it.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_from;
+///
+/// let node = parse(&tokenize("{a..z}{0..999}{0..999}").unwrap()).unwrap();
+/// assert_eq!(expand_from(&node, 1_000_000, 2).unwrap(), vec!["b00", "b01"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_from(
+    node: &crate::parser::Node,
+    cursor: usize,
+    len: usize,
+) -> Result<Vec<String>, ExpansionError> {
+    let mut combinations = Combinations::expand(node, &ExpandOptions::default())?;
+    let mut expanded = vec![];
+    if !combinations.seek(cursor) {
+        return Ok(expanded);
+    }
+    let mut buffer = String::new();
+    while expanded.len() < len && combinations.next_into(&mut buffer) {
+        expanded.push(buffer.clone());
+    }
+    Ok(expanded)
+}
+
+/// Decodes the cursor into the index of the value used for each part, the last part being the
+/// least significant digit. Returns `false` if the cursor is past the last combination.
+fn seek<T>(parts: &[Vec<T>], cursor: usize, indices: &mut [usize]) -> bool {
+    let mut rest = cursor;
+    for (part, i) in parts.iter().zip(indices.iter_mut()).rev() {
+        *i = rest % part.len();
+        rest /= part.len();
+    }
+    rest == 0
+}
+
+/// Expands the parts of the postfix chain of the given parsed node on their own, returning
+/// them along with the count of their combinations, for the expansions which decode an index
+/// into the values of the parts like [expand_from].
+///
+/// A count which doesn't fit in a `usize` is reported as [ExpansionError::LimitExceeded],
+/// pointing at the part which pushed it over.
+#[cfg(any(feature = "rayon", feature = "rand"))]
+fn expand_parts(node: &parser::Node) -> Result<(Vec<Vec<String>>, usize), ExpansionError> {
+    let options = ExpandOptions::default();
+    let mut parts = vec![];
+    let mut total = 1_usize;
+    for part in chain_parts(node) {
+        let values = expand_node(part, &options)?;
+        total = total
+            .checked_mul(values.len())
+            .ok_or(ExpansionError::LimitExceeded {
+                limit: usize::MAX,
+                span: part.span(),
+            })?;
+        parts.push(values);
+    }
+    Ok((parts, total))
+}
+
+/// Picks `n` distinct expansions of the given parsed node at random, e.g. to test against a
+/// few combinations of a huge pattern. Requires the `rand` feature.
+///
+/// The indices of the picked expansions are drawn from the count of combinations, and only
+/// those are built, by decoding each index into the values of the brace groups like
+/// [expand_from]. The same seed picks the same expansions. They are returned in the order of
+/// [expand], and all of them if there are at most `n`.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_sample;
+///
+/// let node = parse(&tokenize("{0..999999}-{0..999999}").unwrap()).unwrap();
+/// let sample = expand_sample(&node, 3, 42).unwrap();
+/// assert_eq!(sample.len(), 3);
+/// assert_eq!(sample, expand_sample(&node, 3, 42).unwrap());
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand], or [ExpansionError::LimitExceeded] if the count of
+/// combinations doesn't fit in a `usize`.
+#[cfg(feature = "rand")]
+pub fn expand_sample(
+    node: &crate::parser::Node,
+    n: usize,
+    seed: u64,
+) -> Result<Vec<String>, ExpansionError> {
+    use rand::SeedableRng;
+    let (parts, total) = expand_parts(node)?;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut picked = rand::seq::index::sample(&mut rng, total, n.min(total)).into_vec();
+    picked.sort_unstable();
+    let mut indices = vec![0; parts.len()];
+    Ok(picked
+        .into_iter()
+        .map(|cursor| {
+            seek(&parts, cursor, &mut indices);
+            parts
+                .iter()
+                .zip(&indices)
+                .map(|(part, &i)| part[i].as_str())
+                .collect()
+        })
+        .collect())
+}
+
+/// Expands the given parsed node into a parallel iterator over the same expansions, in the
+/// same order, as [expand]. Requires the `rayon` feature.
+///
+/// Every item decodes its own index into the values of the brace groups, like
+/// [expand_from], so each worker builds its slice of the expansions independently, and
+/// filtering or mapping fuses into the parallel pipeline. The iterator is indexed, so
+/// collecting it keeps the order. An error expanding a group, or a count which doesn't fit
+/// in a `usize`, is yielded as the only item.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::par_expand_iter;
+/// use rayon::prelude::*;
+///
+/// let node = parse(&tokenize("img{1..3}.{png,jpg}").unwrap()).unwrap();
+/// let pngs: Vec<String> = par_expand_iter(&node)
+///     .filter_map(Result::ok)
+///     .filter(|path| path.ends_with(".png"))
+///     .collect();
+/// assert_eq!(pngs, vec!["img1.png", "img2.png", "img3.png"]);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_expand_iter(
+    node: &crate::parser::Node,
+) -> impl rayon::iter::IndexedParallelIterator<Item = Result<String, ExpansionError>> {
+    use rayon::iter::{Either, IntoParallelIterator, ParallelIterator};
+    match expand_parts(node) {
+        Ok((parts, total)) => Either::Right((0..total).into_par_iter().map(move |cursor| {
+            let mut indices = vec![0; parts.len()];
+            seek(&parts, cursor, &mut indices);
+            Ok(parts
+                .iter()
+                .zip(&indices)
+                .map(|(part, &i)| part[i].as_str())
+                .collect())
+        })),
+        Err(error) => Either::Left(rayon::iter::once(Err(error))),
+    }
+}
+
+/// Expands the given parsed node like [expand], but every alternation (a group seperated by
+/// pipes, e.g. `{a|b}`) only yields its first alternative for which `f` returns `true`.
+///
+/// Each combination of the other groups is kept at most once: the alternatives are tried
+/// from left to right, the leftmost alternation changing slowest, and the first expansion for
+/// which `f` returns `true` is kept, without calling `f` for the rest. If none does, the
+/// combination is dropped. Without any alternation, this keeps the expansions for which `f`
+/// returns `true`, like [expand_filter]. Pipes are only tokenized when
+/// [TokenizerOptions::alternation](tokenizer::TokenizerOptions::alternation) is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::{tokenize_with, TokenizerOptions};
+/// use bracoxide::expand_first_match;
+///
+/// let options = TokenizerOptions::default().alternation(true);
+/// let tokens = tokenize_with("{app,db}/{local|default}.toml", &options).unwrap();
+/// let node = parse(&tokens).unwrap();
+/// let exists = |path: &str| ["app/local.toml", "app/default.toml", "db/default.toml"].contains(&path);
+/// let expanded = expand_first_match(&node, exists).unwrap();
+/// assert_eq!(expanded, vec!["app/local.toml", "db/default.toml"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [expand].
+pub fn expand_first_match<F: Fn(&str) -> bool>(
+    node: &crate::parser::Node,
+    f: F,
+) -> Result<Vec<String>, ExpansionError> {
+    Ok(alternatives(node, &ExpandOptions::default())?
+        .into_iter()
+        .filter_map(|candidates| candidates.into_iter().find(|c| f(c)))
+        .collect())
+}
+
+/// Expands the given parsed node for [expand_first_match]: into the candidates of every
+/// combination of its groups, in the order they are tried. Every alternation contributes all
+/// of its values to the candidates, rather than a combination each.
+fn alternatives(
+    node: &parser::Node,
+    options: &ExpandOptions,
+) -> Result<Vec<Vec<String>>, ExpansionError> {
+    match node {
+        parser::Node::BraceExpansion { .. } => {
+            let mut inner = vec![vec!["".to_owned()]];
+            // Combine from right to left, each part with everything that follows it.
+            for part in chain_parts(node).into_iter().rev() {
+                let mut combined = vec![];
+                for candidates in alternatives(part, options)? {
+                    for rest in &inner {
+                        let mut joined = Vec::with_capacity(candidates.len() * rest.len());
+                        for value in &candidates {
+                            for tail in rest {
+                                joined.push(format!("{}{}", value, tail));
+                            }
                         }
-                    ],
-                    start: 1,
-                    end: 13
-                })),
-                postfix: Some(Box::new(Node::BraceExpansion {
-                    prefix: Some(Box::new(Node::Text {
-                        message: Arc::new("H".into()),
-                        start: 14
-                    })),
-                    inside: Some(Box::new(Node::Collection {
-                        items: vec![
-                            Node::Text {
-                                message: Arc::new("J".into()),
-                                start: 16
-                            },
-                            Node::Text {
-                                message: Arc::new("K".into()),
-                                start: 18
-                            },
-                        ],
-                        start: 15,
-                        end: 19
-                    })),
-                    postfix: Some(Box::new(Node::BraceExpansion {
-                        prefix: Some(Box::new(Node::Text {
-                            message: Arc::new("L".into()),
-                            start: 20
-                        })),
-                        inside: Some(Box::new(Node::Range {
-                            from: Arc::new("3".into()),
-                            to: Arc::new("5".into()),
-                            start: 21,
-                            end: 26
-                        })),
-                        postfix: None,
-                        start: 20,
-                        end: 26
-                    })),
-                    start: 14,
-                    end: 26
-                })),
+                        combined.push(joined);
+                    }
+                }
+                inner = combined;
+            }
+            Ok(inner)
+        }
+        parser::Node::Collection { items, .. } => {
+            let mut inner = vec![];
+            for item in items {
+                inner.extend(alternatives(item, options)?);
+            }
+            Ok(inner)
+        }
+        parser::Node::Alternation { items, .. } => {
+            let mut candidates = vec![];
+            for item in items {
+                candidates.extend(alternatives(item, options)?.into_iter().flatten());
+            }
+            Ok(vec![candidates])
+        }
+        _ => Ok(expand_node(node, options)?
+            .into_iter()
+            .map(|value| vec![value])
+            .collect()),
+    }
+}
+
+/// Counts the combinations the given parsed node expands to, without expanding it.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::count;
+///
+/// let node = parse(&tokenize("{a,b}{1..3}").unwrap()).unwrap();
+/// assert_eq!(count(&node), Ok(6));
+/// ```
+///
+/// # Errors
+///
+/// Returns [ExpansionError::NumConversionFailed] if a range limit isn't a valid number, or
+/// [ExpansionError::LimitExceeded] if the count doesn't fit in `usize`.
+pub fn count(node: &crate::parser::Node) -> Result<usize, ExpansionError> {
+    count_limited(node, &ExpandOptions::default(), usize::MAX)
+}
+
+/// The result of [count_at_most].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CountResult {
+    /// The node expands to exactly this many combinations, which is at most the maximum.
+    Exact(usize),
+    /// The node expands to more combinations than the maximum.
+    Exceeded,
+}
+
+/// Counts the combinations the given parsed node expands to like [count], but stops as soon
+/// as the count passes `max`.
+///
+/// Brace groups are counted from left to right, and counting stops at the first one which
+/// pushes the count over `max`. So patterns whose count wouldn't even fit in a `u128` are
+/// counted cheaply, without overflowing.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::{count_at_most, CountResult};
+///
+/// let node = parse(&tokenize("{a,b}{1..3}").unwrap()).unwrap();
+/// assert_eq!(count_at_most(&node, 6), Ok(CountResult::Exact(6)));
+/// assert_eq!(count_at_most(&node, 5), Ok(CountResult::Exceeded));
+/// ```
+///
+/// # Errors
+///
+/// Returns [ExpansionError::NumConversionFailed] if a range limit isn't a valid number.
+pub fn count_at_most(
+    node: &crate::parser::Node,
+    max: usize,
+) -> Result<CountResult, ExpansionError> {
+    match count_limited(node, &ExpandOptions::default(), max) {
+        Ok(count) => Ok(CountResult::Exact(count)),
+        Err(ExpansionError::LimitExceeded { .. }) => Ok(CountResult::Exceeded),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the deepest nesting level of brace groups in the given parsed node.
+///
+/// Text has a depth of 0, and every brace group adds one level to the deepest of its items.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::max_depth;
+///
+/// let node = parse(&tokenize("{a,{b,{c,d}}}").unwrap()).unwrap();
+/// assert_eq!(max_depth(&node), 3);
+/// ```
+pub fn max_depth(node: &crate::parser::Node) -> usize {
+    match node {
+        parser::Node::Text { .. } => 0,
+        parser::Node::BraceExpansion {
+            prefix,
+            inside,
+            postfix,
+            ..
+        } => [prefix, inside, postfix]
+            .into_iter()
+            .flatten()
+            .map(|n| max_depth(n))
+            .max()
+            .unwrap_or(0),
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            1 + items.iter().map(max_depth).max().unwrap_or(0)
+        }
+        parser::Node::Range { .. }
+        | parser::Node::CharRange { .. }
+        | parser::Node::AlphaRange { .. } => 1,
+    }
+}
+
+/// Returns the length in bytes of the shortest and the longest expansion of the given parsed
+/// node, without producing the expansions, e.g. to plan the width of a column.
+///
+/// The bounds are combined from the leaves up: a text has a fixed length, a brace group spans
+/// the bounds of its items, and the lengths of the groups in a row add up. A numeric range
+/// only measures its values around its ends and around zero. A node without any expansion,
+/// e.g. `{1..<1}`, has bounds of `(0, 0)`.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::length_bounds;
+///
+/// let node = parse(&tokenize("{a,bbbb}{1..10}").unwrap()).unwrap();
+/// assert_eq!(length_bounds(&node), Ok((2, 6)));
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [count].
+pub fn length_bounds(node: &crate::parser::Node) -> Result<(usize, usize), ExpansionError> {
+    Ok(node_length_bounds(node)?.unwrap_or((0, 0)))
+}
+
+/// Returns the bounds of [length_bounds], or `None` if the node has no expansion.
+fn node_length_bounds(node: &parser::Node) -> Result<Option<(usize, usize)>, ExpansionError> {
+    match node {
+        parser::Node::Text { message, .. } => Ok(Some((message.len(), message.len()))),
+        parser::Node::BraceExpansion { .. } => {
+            let mut bounds = (0_usize, 0_usize);
+            for part in chain_parts(node) {
+                let Some((min, max)) = node_length_bounds(part)? else {
+                    return Ok(None);
+                };
+                bounds = (bounds.0.saturating_add(min), bounds.1.saturating_add(max));
+            }
+            Ok(Some(bounds))
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let mut bounds: Option<(usize, usize)> = None;
+            for item in items {
+                if let Some((min, max)) = node_length_bounds(item)? {
+                    bounds = Some(match bounds {
+                        Some(bounds) => (bounds.0.min(min), bounds.1.max(max)),
+                        None => (min, max),
+                    });
+                }
+            }
+            Ok(bounds)
+        }
+        parser::Node::Range {
+            from,
+            to,
+            inclusive,
+            sample_count,
+            step,
+            ..
+        } => range_length_bounds(
+            from,
+            to,
+            *inclusive,
+            sample_count.as_deref(),
+            step.as_deref(),
+        ),
+        parser::Node::CharRange {
+            from,
+            to,
+            inclusive,
+            case,
+            ..
+        } => {
+            let runs = char_range_length_runs(*from, *to, *inclusive, *case);
+            let lengths = runs.iter().map(|(length, _)| *length);
+            Ok(lengths.clone().min().zip(lengths.max()))
+        }
+        parser::Node::AlphaRange { from, .. } => {
+            // every word has the width of the limits.
+            Ok(match range_count(node, &ExpandOptions::default())? {
+                Some(0) => None,
+                _ => Some((from.len(), from.len())),
+            })
+        }
+    }
+}
+
+/// Returns the length in bytes of the shortest and the longest value of a
+/// [parser::Node::Range], or `None` if it has no values.
+fn range_length_bounds(
+    from: &str,
+    to: &str,
+    inclusive: bool,
+    sample_count: Option<&String>,
+    step: Option<&String>,
+) -> Result<Option<(usize, usize)>, ExpansionError> {
+    let runs = range_length_runs(from, to, inclusive, sample_count, step)?;
+    let lengths = runs.iter().map(|(length, _)| *length);
+    Ok(lengths.clone().min().zip(lengths.max()))
+}
+
+/// Returns the lengths in bytes of the values of a [parser::Node::Range], from the first
+/// value to the last one, as runs of values with the same length: the length and the number
+/// of values in the run.
+///
+/// The values are monotonic, and the length of a value only grows with its magnitude on
+/// either side of zero. So the values are split where the sign changes, and the end of each
+/// run is found with a binary search, which only measures a few values per digit.
+fn range_length_runs(
+    from: &str,
+    to: &str,
+    inclusive: bool,
+    sample_count: Option<&String>,
+    step: Option<&String>,
+) -> Result<Vec<(usize, u128)>, ExpansionError> {
+    let Some((first, last)) = range_bounds(from, to, inclusive, step)? else {
+        return Ok(vec![]);
+    };
+    let distance = first.abs_diff(last);
+    let (count, step) = match sample_count {
+        Some(n) => (parse_sample_count(n)? as u128, None),
+        None => {
+            let step = parse_step(step)? as u128;
+            (distance / step + 1, Some(step))
+        }
+    };
+    let value = |i: u128| {
+        let offset = match step {
+            Some(step) => i * step,
+            None => sample_offset(i, count, distance),
+        } as i128;
+        match first <= last {
+            true => first + offset,
+            false => first - offset,
+        }
+    };
+    let width = range_width(from, to);
+    let length = |i: u128| format_range_value(value(i), from, to, width).len();
+    // the index of the first value with the other sign than the first value, if any.
+    let is_negative = first < 0;
+    let (mut low, mut high) = (1, count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match (value(mid) < 0) == is_negative {
+            true => low = mid + 1,
+            false => high = mid,
+        }
+    }
+    let mut runs = vec![];
+    for (mut start, end) in [(0, low), (low, count)] {
+        while start < end {
+            // the length is monotonic up to `end`, find the last value with the same length.
+            let run_length = length(start);
+            let (mut low, mut high) = (start + 1, end);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                match length(mid) == run_length {
+                    true => low = mid + 1,
+                    false => high = mid,
+                }
+            }
+            runs.push((run_length, low - start));
+            start = low;
+        }
+    }
+    Ok(runs)
+}
+
+/// Returns the lengths in bytes of the values of a [parser::Node::CharRange] expanded with the
+/// default options, as runs of values with the same length like [range_length_runs], from the
+/// lowest codepoint up.
+///
+/// The length of a char only grows with its codepoint, so without a case conversion the runs
+/// are the codepoints encoded in as many bytes, less the surrogates. A converted char may be
+/// longer or shorter, so each one is measured then, without producing the values.
+fn char_range_length_runs(
+    from: char,
+    to: char,
+    inclusive: bool,
+    case: Option<parser::Case>,
+) -> Vec<(usize, u128)> {
+    let (codepoints, _) = char_range_codepoints(from, to, inclusive);
+    let Some(case) = case else {
+        return [0..0x80, 0x80..0x800, 0x800..0x10000, 0x10000..0x110000]
+            .into_iter()
+            .zip(1..)
+            .map(|(band, length)| {
+                let within = codepoints_within(&codepoints, band);
+                let count = within.len() - codepoints_within(&within, SURROGATES).len();
+                (length, count as u128)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+    };
+    let mut runs: Vec<(usize, u128)> = vec![];
+    for c in char_range_chars(codepoints, CharRangeMode::Raw) {
+        let length = match case {
+            parser::Case::Upper => c.to_uppercase().map(char::len_utf8).sum(),
+            parser::Case::Lower => c.to_lowercase().map(char::len_utf8).sum(),
+        };
+        match runs.last_mut() {
+            Some((last, count)) if *last == length => *count += 1,
+            _ => runs.push((length, 1)),
+        }
+    }
+    runs
+}
+
+/// Returns the total length in bytes of all the expansions of the given parsed node, without
+/// producing them, e.g. to check whether the expansion fits in memory before running it.
+///
+/// The total is exact, it's the sum of the lengths of the strings [expand] returns, without
+/// the overhead of the [String]s and the [Vec] holding them. It's combined from the leaves
+/// up, along with the count of expansions: every expansion of a group in a row is repeated
+/// once for each combination of the rest of the row.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::estimated_bytes;
+///
+/// // a1, a2, ..., a10, bb1, ..., bb10
+/// let node = parse(&tokenize("{a,bb}{1..10}").unwrap()).unwrap();
+/// assert_eq!(estimated_bytes(&node), Ok(52));
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [count], and [ExpansionError::LimitExceeded] if the total
+/// doesn't fit in `usize`.
+pub fn estimated_bytes(node: &crate::parser::Node) -> Result<usize, ExpansionError> {
+    Ok(node_bytes(node)?.1)
+}
+
+/// Returns the count of expansions of the given parsed node along with their total length in
+/// bytes, see [estimated_bytes].
+fn node_bytes(node: &parser::Node) -> Result<(usize, usize), ExpansionError> {
+    let exceeded = |node: &parser::Node| ExpansionError::LimitExceeded {
+        limit: usize::MAX,
+        span: node.span(),
+    };
+    match node {
+        parser::Node::Text { message, .. } => Ok((1, message.len())),
+        parser::Node::BraceExpansion { .. } => {
+            let (mut count, mut bytes) = (1_usize, 0_usize);
+            for part in chain_parts(node) {
+                let (part_count, part_bytes) = node_bytes(part)?;
+                // every expansion so far is followed by every value of the part, and the
+                // other way around.
+                bytes = bytes
+                    .checked_mul(part_count)
+                    .zip(part_bytes.checked_mul(count))
+                    .and_then(|(bytes, part_bytes)| bytes.checked_add(part_bytes))
+                    .ok_or_else(|| exceeded(part))?;
+                count = count
+                    .checked_mul(part_count)
+                    .ok_or_else(|| exceeded(part))?;
+            }
+            Ok((count, bytes))
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let (mut count, mut bytes) = (0_usize, 0_usize);
+            for item in items {
+                let (item_count, item_bytes) = node_bytes(item)?;
+                count = count
+                    .checked_add(item_count)
+                    .ok_or_else(|| exceeded(node))?;
+                bytes = bytes
+                    .checked_add(item_bytes)
+                    .ok_or_else(|| exceeded(node))?;
+            }
+            Ok((count, bytes))
+        }
+        parser::Node::Range {
+            from,
+            to,
+            inclusive,
+            sample_count,
+            step,
+            ..
+        } => {
+            let runs = range_length_runs(
+                from,
+                to,
+                *inclusive,
+                sample_count.as_deref(),
+                step.as_deref(),
+            )?;
+            runs_bytes(&runs).ok_or_else(|| exceeded(node))
+        }
+        parser::Node::CharRange {
+            from,
+            to,
+            inclusive,
+            case,
+            ..
+        } => runs_bytes(&char_range_length_runs(*from, *to, *inclusive, *case))
+            .ok_or_else(|| exceeded(node)),
+        parser::Node::AlphaRange { from, .. } => {
+            // every word has the width of the limits.
+            let count =
+                range_count(node, &ExpandOptions::default())?.ok_or_else(|| exceeded(node))?;
+            Ok((
+                count,
+                count
+                    .checked_mul(from.len())
+                    .ok_or_else(|| exceeded(node))?,
+            ))
+        }
+    }
+}
+
+/// Returns the count of values in the given runs of values with the same length, see
+/// [range_length_runs], and their length in bytes in total, or `None` if either doesn't fit
+/// in `usize`.
+fn runs_bytes(runs: &[(usize, u128)]) -> Option<(usize, usize)> {
+    let count = runs.iter().map(|(_, count)| count).sum::<u128>();
+    let bytes = runs.iter().try_fold(0_u128, |bytes, (length, count)| {
+        bytes.checked_add((*length as u128).checked_mul(*count)?)
+    })?;
+    Some((usize::try_from(count).ok()?, usize::try_from(bytes).ok()?))
+}
+
+/// Normalizes the given parsed node, so equivalent patterns get the same node, e.g. to key a
+/// cache by the pattern. Its [Display](std::fmt::Display) is the canonical pattern.
+///
+/// The expansions are kept, in the same order. The normalization:
+///
+/// * drops redundant braces around a single item, e.g. `{a}b` and `{{a,b}}` become `ab` and
+///   `{a,b}`,
+/// * splices nested collections into their collection, e.g. `{{a,b},c}` becomes `{a,b,c}`,
+/// * merges adjacent texts, e.g. `{a}{b}` becomes `ab`.
+///
+/// Duplicates are kept, `{a,a,b}` expands to `a` twice. Alternations are kept as they are, as
+/// they only differ from collections in [expand_first_match]. The positions of the canonical
+/// node are those of the canonical pattern.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::canonicalize;
+///
+/// let node = parse(&tokenize("{x}{{a,b},{c}}{1..3}").unwrap()).unwrap();
+/// let canonical = canonicalize(&node);
+/// assert_eq!(canonical.to_string(), "x{a,b,c}{1..3}");
+/// assert_eq!(canonical, canonicalize(&parse(&tokenize("x{a,{b,c}}{1..3}").unwrap()).unwrap()));
+/// ```
+pub fn canonicalize(node: &crate::parser::Node) -> crate::parser::Node {
+    let normalized = normalize(node);
+    let options = tokenizer::TokenizerOptions::default().alternation(true);
+    match tokenizer::tokenize_with(&normalized.to_string(), &options) {
+        Ok(tokens) => parser::parse(&tokens).unwrap_or(normalized),
+        // only text is left, e.g. `{a}{b}`.
+        Err(_) => match normalized {
+            parser::Node::Text { message, .. } => parser::Node::Text {
+                end: message.chars().count(),
+                message,
+                start: 0,
+            },
+            normalized => normalized,
+        },
+    }
+}
+
+/// Normalizes the given parsed node for [canonicalize]. The positions of the nodes it builds
+/// are 0, they are only known once the canonical pattern is parsed.
+fn normalize(node: &parser::Node) -> parser::Node {
+    match node {
+        parser::Node::BraceExpansion { .. } => {
+            let mut parts = vec![];
+            for part in chain_parts(node) {
+                match normalize(part) {
+                    // a single-item group normalizes into its item, which may be a chain.
+                    part @ parser::Node::BraceExpansion { .. } => {
+                        parts.extend(chain_parts(&part).into_iter().cloned())
+                    }
+                    part => parts.push(part),
+                }
+            }
+            chain(parts)
+        }
+        parser::Node::Collection { items, .. } => {
+            let mut normalized = vec![];
+            for item in items {
+                match normalize(item) {
+                    parser::Node::Collection { items, .. } => normalized.extend(items),
+                    item => normalized.push(item),
+                }
+            }
+            if normalized.len() == 1 {
+                return normalized.remove(0);
+            }
+            parser::Node::Collection {
+                items: normalized,
+                start: 0,
+                end: 0,
+            }
+        }
+        parser::Node::Alternation { items, .. } => parser::Node::Alternation {
+            items: items.iter().map(normalize).collect(),
+            start: 0,
+            end: 0,
+        },
+        node => node.clone(),
+    }
+}
+
+/// Expands only the brace group at the given index of the given parsed node, and writes the
+/// rest of the node back as a pattern around every value of that group, so the other groups
+/// can be expanded in a later stage, e.g. `{prod,dev}/{a,b}` gives `prod/{a,b}` and
+/// `dev/{a,b}`.
+///
+/// The groups are indexed from 0 in the order they appear. A group nested in another one is
+/// part of the outer group, so `{a,{b,c}}/{1..3}` has two groups. The patterns are written
+/// with the [Display](std::fmt::Display) of [parser::Node], so the value and the texts are
+/// escaped where needed.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_group;
+///
+/// let node = parse(&tokenize("{prod,dev}/{a,b}/{1..3}").unwrap()).unwrap();
+/// assert_eq!(
+///     expand_group(&node, 0).unwrap(),
+///     vec!["prod/{a,b}/{1..3}", "dev/{a,b}/{1..3}"]
+/// );
+/// assert_eq!(expand_group(&node, 2).unwrap()[0], "{prod,dev}/{a,b}/1");
+/// ```
+///
+/// # Errors
+///
+/// Returns [ExpansionError::GroupNotFound] if there are fewer groups, or the same errors as
+/// [expand] while expanding the group.
+pub fn expand_group(
+    node: &crate::parser::Node,
+    group: usize,
+) -> Result<Vec<String>, ExpansionError> {
+    let parts: Vec<_> = chain_groups(node).collect();
+    let index = (0..parts.len())
+        .filter(|&i| parts[i].1)
+        .nth(group)
+        .ok_or(ExpansionError::GroupNotFound(group))?;
+    let selected = parts[index].0;
+    let parts: Vec<_> = parts
+        .into_iter()
+        .map(|(part, is_group)| match part {
+            // a text in braces, e.g. `{a}`, stays a brace group.
+            parser::Node::Text { .. } if is_group => parser::Node::Collection {
+                items: vec![part.clone()],
+                start: 0,
+                end: 0,
+            },
+            part => part.clone(),
+        })
+        .collect();
+    let mut expanded = vec![];
+    for value in expand(selected)? {
+        let mut resolved = parts.clone();
+        resolved[index] = parser::Node::Text {
+            end: value.chars().count(),
+            message: std::sync::Arc::new(value),
+            start: 0,
+        };
+        expanded.push(chain(resolved).to_string());
+    }
+    Ok(expanded)
+}
+
+/// Expands the combination of the given parsed node which takes the given value of each brace
+/// group, e.g. `[1, 0]` picks `b` and `1` from `{a,b}/{1..3}`, giving `b/1`.
+///
+/// The groups are indexed like in [expand_group], a group nested in another one is part of
+/// the outer group, and each choice is the index of a value of its group, as [expand] would
+/// produce them. So the choices of the combinations are the digits of their indices in the
+/// result of [expand], the last group changing fastest.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_selection;
+///
+/// let node = parse(&tokenize("{a,{b,c}}-{1..3}.{txt,md}").unwrap()).unwrap();
+/// assert_eq!(expand_selection(&node, &[2, 0, 1]).unwrap(), "c-1.md");
+/// ```
+///
+/// # Errors
+///
+/// Returns [ExpansionError::ChoiceOutOfRange] if a choice is past the values of its group,
+/// [ExpansionError::GroupNotFound] if there are more choices than groups, or
+/// [ExpansionError::ChoiceMissing] if there are fewer, and the same errors as [expand] while
+/// expanding a group.
+pub fn expand_selection(
+    node: &crate::parser::Node,
+    choices: &[usize],
+) -> Result<String, ExpansionError> {
+    let options = ExpandOptions::default();
+    let mut group = 0;
+    let mut expanded = String::new();
+    for (part, is_group) in chain_groups(node) {
+        let values = expand_node(part, &options)?;
+        if !is_group {
+            expanded.push_str(&values.concat());
+            continue;
+        }
+        let index = *choices
+            .get(group)
+            .ok_or(ExpansionError::ChoiceMissing(group))?;
+        let value = values.get(index).ok_or(ExpansionError::ChoiceOutOfRange {
+            group,
+            index,
+            len: values.len(),
+        })?;
+        expanded.push_str(value);
+        group += 1;
+    }
+    match choices.len() > group {
+        true => Err(ExpansionError::GroupNotFound(group)),
+        false => Ok(expanded),
+    }
+}
+
+/// Walks the postfix chain of `node`, yielding its parts like [chain_parts], each with whether
+/// it's a brace group. A text in braces, e.g. `{a}`, is a group too.
+fn chain_groups(node: &parser::Node) -> impl Iterator<Item = (&parser::Node, bool)> {
+    std::iter::successors(Some(node), |node| match node {
+        parser::Node::BraceExpansion { postfix, .. } => postfix.as_deref(),
+        _ => None,
+    })
+    .flat_map(|node| match node {
+        parser::Node::BraceExpansion { prefix, inside, .. } => [
+            prefix.as_deref().map(|prefix| (prefix, false)),
+            inside.as_deref().map(|inside| (inside, true)),
+        ],
+        part => [
+            Some((part, !matches!(part, parser::Node::Text { .. }))),
+            None,
+        ],
+    })
+    .flatten()
+}
+
+/// Builds a postfix chain out of the given parts, the reverse of [chain_parts]. Adjacent texts
+/// are merged and empty texts are dropped. A single part is returned as is. The positions of
+/// the nodes it builds are 0.
+fn chain(parts: Vec<parser::Node>) -> parser::Node {
+    let mut merged: Vec<parser::Node> = vec![];
+    for part in parts {
+        match (merged.last_mut(), part) {
+            (_, parser::Node::Text { message, .. }) if message.is_empty() => (),
+            (
+                Some(parser::Node::Text { message: last, .. }),
+                parser::Node::Text { message, .. },
+            ) => std::sync::Arc::make_mut(last).push_str(&message),
+            (_, part) => merged.push(part),
+        }
+    }
+    if merged.len() == 1 {
+        return merged.remove(0);
+    }
+    // Rebuild the chain from right to left, every group taking the text before it as its
+    // prefix.
+    let mut postfix = match merged.last() {
+        Some(parser::Node::Text { .. }) => merged.pop().map(Box::new),
+        _ => None,
+    };
+    while let Some(inside) = merged.pop() {
+        let prefix = match merged.last() {
+            Some(parser::Node::Text { .. }) => merged.pop().map(Box::new),
+            _ => None,
+        };
+        postfix = Some(Box::new(parser::Node::BraceExpansion {
+            prefix,
+            inside: Some(Box::new(inside)),
+            postfix,
+            start: 0,
+            end: 0,
+        }));
+    }
+    match postfix {
+        Some(node) => *node,
+        None => parser::Node::Text {
+            message: std::sync::Arc::new(String::new()),
+            start: 0,
+            end: 0,
+        },
+    }
+}
+
+/// Flattens the postfix chain of `node`, e.g. `a{b,c}d{e,f}`, into its parts from left to
+/// right: the prefix and the brace group of every [parser::Node::BraceExpansion] in the
+/// chain, and the postfix of the last one. Any other node is a part of its own.
+fn chain_parts(node: &parser::Node) -> Vec<&parser::Node> {
+    chain_groups(node).map(|(part, _)| part).collect()
+}
+
+/// Expands the parts of a postfix chain made of a brace group of plain texts, with an optional
+/// plain prefix and postfix, e.g. `x{red,green,blue}y`. Returns `None` for any other parts.
+///
+/// This is the most common pattern, so it skips the generic combination of the parts and
+/// builds every expansion at its final size right away.
+fn expand_flat_collection(parts: &[&parser::Node]) -> Option<Vec<String>> {
+    fn text(node: &parser::Node) -> Option<&str> {
+        match node {
+            parser::Node::Text { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+    let (prefix, items, postfix) = match parts {
+        [parser::Node::Collection { items, .. }] => ("", items, ""),
+        [prefix, parser::Node::Collection { items, .. }] => (text(prefix)?, items, ""),
+        [parser::Node::Collection { items, .. }, postfix] => ("", items, text(postfix)?),
+        [prefix, parser::Node::Collection { items, .. }, postfix] => {
+            (text(prefix)?, items, text(postfix)?)
+        }
+        _ => return None,
+    };
+    let mut expansions = Vec::with_capacity(items.len());
+    for item in items {
+        let item = text(item)?;
+        let mut expansion = String::with_capacity(prefix.len() + item.len() + postfix.len());
+        expansion.push_str(prefix);
+        expansion.push_str(item);
+        expansion.push_str(postfix);
+        expansions.push(expansion);
+    }
+    Some(expansions)
+}
+
+/// Counts the combinations of `node`, failing as soon as the count exceeds `limit`.
+///
+/// Brace groups are counted in the order they appear, so the reported span points at the group
+/// which pushed the count over the limit.
+fn count_limited(
+    node: &crate::parser::Node,
+    options: &ExpandOptions,
+    limit: usize,
+) -> Result<usize, ExpansionError> {
+    let exceeded = |span| ExpansionError::LimitExceeded { limit, span };
+    match node {
+        parser::Node::Text { .. } => Ok(1),
+        parser::Node::BraceExpansion { .. } => {
+            let mut product = 1_usize;
+            // Count the parts of the postfix chain from left to right.
+            for part in chain_parts(node) {
+                let count = count_limited(part, options, limit)?;
+                product = match product.checked_mul(count) {
+                    Some(product) if product <= limit => product,
+                    _ => return Err(exceeded(part.span())),
+                };
+            }
+            Ok(product)
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let mut sum = 0_usize;
+            for item in items {
+                let count = count_limited(item, options, limit)?;
+                sum = match sum.checked_add(count) {
+                    Some(sum) if sum <= limit => sum,
+                    _ => return Err(exceeded(node.span())),
+                };
+            }
+            Ok(sum)
+        }
+        parser::Node::Range { .. }
+        | parser::Node::CharRange { .. }
+        | parser::Node::AlphaRange { .. } => {
+            let count = range_count(node, options)?;
+            check_range_len(node, options, count)?;
+            match count {
+                Some(count) if count <= limit => Ok(count),
+                _ => Err(exceeded(node.span())),
+            }
+        }
+    }
+}
+
+/// Counts the values of the given range node, or returns `None` if they don't fit in `usize`.
+/// Any other node counts as a single value.
+fn range_count(
+    node: &parser::Node,
+    options: &ExpandOptions,
+) -> Result<Option<usize>, ExpansionError> {
+    Ok(match node {
+        parser::Node::Range {
+            from,
+            to,
+            inclusive,
+            sample_count,
+            step,
+            ..
+        } => match (
+            range_bounds(from, to, *inclusive, step.as_deref())?,
+            sample_count,
+        ) {
+            (None, _) => Some(0),
+            (Some((first, last)), None) => {
+                let step = parse_step(step.as_deref())?;
+                usize::try_from(first.abs_diff(last) / step as u128)
+                    .ok()
+                    .and_then(|steps| steps.checked_add(1))
+            }
+            (Some(_), Some(n)) => Some(parse_sample_count(n)?),
+        },
+        parser::Node::CharRange {
+            from,
+            to,
+            inclusive,
+            ..
+        } => Some(char_range_count(
+            *from,
+            *to,
+            *inclusive,
+            options.char_range_mode,
+        )),
+        parser::Node::AlphaRange {
+            from,
+            to,
+            inclusive,
+            ..
+        } => {
+            // Words too wide to be indexed have way too many values anyway.
+            match (alpha_index(from), alpha_index(to)) {
+                (Some(from), Some(to)) => {
+                    usize::try_from(from.abs_diff(to))
+                        .ok()
+                        .and_then(|diff| match inclusive {
+                            true => diff.checked_add(1),
+                            false => Some(diff),
+                        })
+                }
+                _ => None,
+            }
+        }
+        _ => Some(1),
+    })
+}
+
+/// Returns the codepoints a [parser::Node::CharRange] spans, lowest first, and whether its
+/// values run from the highest one down. Some of them may not be chars, e.g. surrogates.
+fn char_range_codepoints(from: char, to: char, inclusive: bool) -> (std::ops::Range<u32>, bool) {
+    let (from, to) = (from as u32, to as u32);
+    match (from <= to, inclusive) {
+        (true, true) => (from..to + 1, false),
+        (true, false) => (from..to, false),
+        (false, true) => (to..from + 1, true),
+        (false, false) => (to + 1..from + 1, true),
+    }
+}
+
+/// Returns the chars among the given codepoints which the [CharRangeMode] keeps, before their
+/// case is converted.
+fn char_range_chars(
+    codepoints: std::ops::Range<u32>,
+    mode: CharRangeMode,
+) -> impl DoubleEndedIterator<Item = char> {
+    codepoints
+        // skip the values which aren't chars, e.g. surrogates.
+        .filter_map(char::from_u32)
+        .filter(move |c| match mode {
+            CharRangeMode::Raw => true,
+            CharRangeMode::AlnumOnly => c.is_alphanumeric(),
+        })
+}
+
+/// Returns the given codepoints which are in `band`.
+fn codepoints_within(
+    codepoints: &std::ops::Range<u32>,
+    band: std::ops::Range<u32>,
+) -> std::ops::Range<u32> {
+    codepoints.start.max(band.start)..codepoints.end.min(band.end)
+}
+
+/// The surrogates, the only codepoints which aren't chars.
+const SURROGATES: std::ops::Range<u32> = 0xD800..0xE000;
+
+/// Counts the values of a [parser::Node::CharRange], without producing them.
+fn char_range_count(from: char, to: char, inclusive: bool, mode: CharRangeMode) -> usize {
+    let (codepoints, _) = char_range_codepoints(from, to, inclusive);
+    match mode {
+        CharRangeMode::Raw => codepoints.len() - codepoints_within(&codepoints, SURROGATES).len(),
+        CharRangeMode::AlnumOnly => char_range_chars(codepoints, mode).count(),
+    }
+}
+
+/// Checks the count of values of the given range node, as returned by [range_count], against
+/// [ExpandOptions::max_range_len].
+fn check_range_len(
+    node: &parser::Node,
+    options: &ExpandOptions,
+    count: Option<usize>,
+) -> Result<(), ExpansionError> {
+    match (options.max_range_len, count) {
+        (Some(max), Some(count)) if count <= max => Ok(()),
+        // a count too large for a usize is over any limit.
+        (Some(max), _) => Err(ExpansionError::RangeTooLarge {
+            max,
+            span: node.span(),
+        }),
+        (None, _) => Ok(()),
+    }
+}
+
+/// Returns the index of the given word of a [parser::Node::AlphaRange], counting `a` (or `A`)
+/// as 0 and `z` (or `Z`) as 25, or `None` if the index overflows.
+fn alpha_index(word: &str) -> Option<u128> {
+    word.bytes().try_fold(0_u128, |index, b| {
+        index
+            .checked_mul(26)?
+            .checked_add(u128::from(b.to_ascii_lowercase() - b'a'))
+    })
+}
+
+/// Steps the given word of a [parser::Node::AlphaRange] to the next (or the previous) one,
+/// carrying over like the columns of a spreadsheet, e.g. `az` is followed by `ba`.
+fn alpha_step(word: &mut [u8], ascending: bool) {
+    for b in word.iter_mut().rev() {
+        let (first, last) = match b.is_ascii_lowercase() {
+            true => (b'a', b'z'),
+            false => (b'A', b'Z'),
+        };
+        match (ascending, *b) {
+            (true, c) if c == last => *b = first,
+            (false, c) if c == first => *b = last,
+            (true, _) => {
+                *b += 1;
+                return;
+            }
+            (false, _) => {
+                *b -= 1;
+                return;
+            }
+        }
+    }
+}
+
+/// Parses the limits of a [parser::Node::Range], returning its first and last value, or
+/// `None` if the range is empty. The first value is greater than the last one if the range
+/// is descending, e.g. `{3..1}`.
+///
+/// A limit may be negative, e.g. `{-3..3}`, but its magnitude must fit in a `usize`. A step
+/// with an explicit sign going the other way than the limits, e.g. `{1..10:-2}`, leaves the
+/// range empty, like walking down from 1 never reaches 10.
+fn range_bounds(
+    from: &str,
+    to: &str,
+    inclusive: bool,
+    step: Option<&String>,
+) -> Result<Option<(i128, i128)>, ExpansionError> {
+    let parse = |limit: &str| {
+        let digits = limit.strip_prefix('-').unwrap_or(limit);
+        let magnitude = match hex_digits(limit) {
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => digits.parse::<usize>(),
+        };
+        let value = magnitude.map(|v| match limit.starts_with('-') {
+            true => -(v as i128),
+            false => v as i128,
+        });
+        value.map_err(|_| ExpansionError::NumConversionFailed(limit.to_string()))
+    };
+    let from = parse(from)?;
+    let to = parse(to)?;
+    let bounds = match (inclusive, from.cmp(&to)) {
+        (true, _) => Some((from, to)),
+        (false, std::cmp::Ordering::Less) => Some((from, to - 1)),
+        (false, std::cmp::Ordering::Equal) => None,
+        (false, std::cmp::Ordering::Greater) => Some((from, to + 1)),
+    };
+    Ok(bounds.filter(
+        |(first, last)| match step.and_then(|step| step.chars().next()) {
+            Some('+') => first <= last,
+            Some('-') => first >= last,
+            _ => true,
+        },
+    ))
+}
+
+/// Returns the width the values of a [parser::Node::Range] are zero-padded to, like bash does.
+///
+/// The values are only padded if a limit is written with leading zeros, e.g. `{01..10}` or
+/// `{-01..1}`, to the width of the wider limit, sign included. Otherwise the width is 0.
+///
+/// The values of a hex range are always padded to the digits of the wider limit, without its
+/// sign and its `0x`, e.g. `{0x0..0x10}` gives `00` to `10`.
+fn range_width(from: &str, to: &str) -> usize {
+    if let Some((from, to)) = hex_digits(from).zip(hex_digits(to)) {
+        return from.len().max(to.len());
+    }
+    let is_padded = |limit: &str| {
+        let digits = limit.strip_prefix('-').unwrap_or(limit);
+        digits.len() > 1 && digits.starts_with('0')
+    };
+    match is_padded(from) || is_padded(to) {
+        true => from.len().max(to.len()),
+        false => 0,
+    }
+}
+
+/// Returns the digits of a limit of a [parser::Node::Range] written in hex, e.g. `ff` for
+/// `0xff` or `-0xff`, or `None` if it's written in decimal.
+fn hex_digits(limit: &str) -> Option<&str> {
+    limit.strip_prefix('-').unwrap_or(limit).strip_prefix("0x")
+}
+
+/// Writes a value of a [parser::Node::Range] with the given limits, zero-padded to the width
+/// [range_width] returns. The values of a hex range are written in hex, in uppercase if a
+/// limit has uppercase digits, e.g. `{0x0..0xFF}`.
+fn format_range_value(value: i128, from: &str, to: &str, width: usize) -> String {
+    let Some((hex_from, hex_to)) = hex_digits(from).zip(hex_digits(to)) else {
+        return format!("{:0width$}", value);
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    match (hex_from.bytes().chain(hex_to.bytes())).any(|b| b.is_ascii_uppercase()) {
+        true => format!("{sign}{magnitude:0width$X}"),
+        false => format!("{sign}{magnitude:0width$x}"),
+    }
+}
+
+/// Parses the magnitude of the step of a [parser::Node::Range] like bash: a missing step or a
+/// step of 0 is 1. The direction is up to [range_bounds].
+fn parse_step(step: Option<&String>) -> Result<usize, ExpansionError> {
+    let Some(step) = step else {
+        return Ok(1);
+    };
+    match step
+        .strip_prefix(['+', '-'])
+        .unwrap_or(step)
+        .parse::<usize>()
+    {
+        Ok(step) => Ok(step.max(1)),
+        Err(_) => Err(ExpansionError::NumConversionFailed(step.to_string())),
+    }
+}
+
+/// Parses the sample count of a [parser::Node::Range], which must be at least 2.
+fn parse_sample_count(count: &str) -> Result<usize, ExpansionError> {
+    match count.parse::<usize>() {
+        Ok(count) if count < 2 => Err(ExpansionError::SampleCountTooSmall(count)),
+        Ok(count) => Ok(count),
+        Err(_) => Err(ExpansionError::NumConversionFailed(count.to_string())),
+    }
+}
+
+/// Returns the offset of the `i`-th of `n` values spread evenly over the given distance, see
+/// [range_values].
+fn sample_offset(i: u128, n: u128, distance: u128) -> u128 {
+    (2 * i * distance + (n - 1)) / (2 * (n - 1))
+}
+
+/// Returns the values of a [parser::Node::Range], from the first to the last one.
+fn range_values(
+    from: &str,
+    to: &str,
+    inclusive: bool,
+    sample_count: Option<&String>,
+    step: Option<&String>,
+) -> Result<Vec<i128>, ExpansionError> {
+    let mut values = vec![];
+    match (range_bounds(from, to, inclusive, step)?, sample_count) {
+        (None, _) => (),
+        (Some((first, last)), None) if first <= last => {
+            values.extend((first..=last).step_by(parse_step(step)?));
+        }
+        (Some((first, last)), None) => {
+            values.extend((last..=first).rev().step_by(parse_step(step)?));
+        }
+        (Some((first, last)), Some(n)) => {
+            // `n` values spread evenly from the first value to the last, each one
+            // rounded to the nearest integer (halves are rounded away from first).
+            let n = parse_sample_count(n)? as u128;
+            let distance = first.abs_diff(last);
+            for i in 0..n {
+                // the offset is at most the distance, which fits in an i128.
+                let offset = sample_offset(i, n, distance) as i128;
+                values.push(match first <= last {
+                    true => first + offset,
+                    false => first - offset,
+                });
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Expands the given parsed node, without checking the limit of [ExpandOptions].
+fn expand_node(
+    node: &crate::parser::Node,
+    options: &ExpandOptions,
+) -> Result<Vec<String>, ExpansionError> {
+    match node {
+        parser::Node::Text { message, .. } => Ok(vec![message.as_ref().to_owned()]),
+        parser::Node::BraceExpansion { .. } => {
+            let nodes = chain_parts(node);
+            if options.max_output_bytes.is_none() {
+                if let Some(expansions) = expand_flat_collection(&nodes) {
+                    return Ok(expansions);
+                }
+            }
+            // Expand the parts of the postfix chain on their own, rather than recursing into
+            // every postfix, a long chain of groups would overflow the stack otherwise.
+            let mut parts = vec![];
+            for part in nodes {
+                parts.push(expand_node(part, options)?);
+            }
+            let mut combinations = Combinations::new(parts);
+            let mut expanded = vec![];
+            // every expansion counts towards the maximum, like in Expander::expand.
+            let mut bytes = 0_usize;
+            let mut buffer = String::new();
+            while combinations.next_into(&mut buffer) {
+                if let Some(max) = options.max_output_bytes {
+                    bytes = bytes.saturating_add(buffer.len());
+                    if bytes > max {
+                        return Err(ExpansionError::OutputTooLarge { bytes, max });
+                    }
+                }
+                expanded.push(buffer.clone());
+            }
+            Ok(expanded)
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let mut inner = vec![];
+            for item in items {
+                let expansions = expand_node(item, options)?;
+                inner.extend(expansions);
+            }
+            Ok(inner)
+        }
+        parser::Node::Range {
+            from,
+            to,
+            inclusive,
+            sample_count,
+            step,
+            start: _,
+            end: _,
+        } => {
+            if options.max_range_len.is_some() {
+                check_range_len(node, options, range_count(node, options)?)?;
+            }
+            let width = range_width(from, to);
+            let render = |i: i128| match &options.range_format {
+                Some(RangeFormat(format)) => format(i),
+                // the digits of hex values aren't grouped.
+                None if hex_digits(from).is_some() => format_range_value(i, from, to, width),
+                None => options.number_format.format(i, width),
+            };
+            let values = range_values(
+                from,
+                to,
+                *inclusive,
+                sample_count.as_deref(),
+                step.as_deref(),
+            )?;
+            Ok(values.into_iter().map(render).collect())
+        }
+        parser::Node::CharRange {
+            from,
+            to,
+            inclusive,
+            case,
+            start: _,
+            end: _,
+        } => {
+            if options.max_range_len.is_some() {
+                check_range_len(node, options, range_count(node, options)?)?;
+            }
+            let (codepoints, descending) = char_range_codepoints(*from, *to, *inclusive);
+            let chars = char_range_chars(codepoints, options.char_range_mode);
+            let chars: Box<dyn Iterator<Item = char>> = match descending {
+                true => Box::new(chars.rev()),
+                false => Box::new(chars),
+            };
+            Ok(chars
+                .map(|c| match case {
+                    Some(parser::Case::Upper) => c.to_uppercase().collect(),
+                    Some(parser::Case::Lower) => c.to_lowercase().collect(),
+                    None => String::from(c),
+                })
+                .collect())
+        }
+        parser::Node::AlphaRange {
+            from,
+            to,
+            inclusive,
+            start: _,
+            end: _,
+        } => {
+            if options.max_range_len.is_some() {
+                check_range_len(node, options, range_count(node, options)?)?;
+            }
+            let mut inner = vec![];
+            let ascending = from <= to;
+            let mut word = from.as_bytes().to_vec();
+            loop {
+                if word == to.as_bytes() {
+                    if *inclusive {
+                        inner.push(String::from_utf8_lossy(&word).into_owned());
+                    }
+                    break;
+                }
+                inner.push(String::from_utf8_lossy(&word).into_owned());
+                alpha_step(&mut word, ascending);
+            }
+            Ok(inner)
+        }
+    }
+}
+
+/// A choice made in a brace group while producing a [SpannedExpansion].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Choice {
+    /// Span of the brace group, including both braces.
+    pub group: std::ops::Range<usize>,
+    /// Span of the chosen item inside the group. For ranges, this is the span of the
+    /// whole group, since the value is generated rather than written.
+    pub item: std::ops::Range<usize>,
+}
+
+/// A single expanded value, along with the choices which produced it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedExpansion {
+    /// The expanded value.
+    pub value: String,
+    /// The choices made in each brace group, in the order the groups appear.
+    pub choices: Vec<Choice>,
+}
+
+/// Expands the given parsed node like [expand], additionally recording which item of which
+/// brace group each expanded value originates from.
+///
+/// This allows mapping an expanded value back to the pattern, e.g. to highlight the
+/// originating brace groups.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_spanned;
+///
+/// let node = parse(&tokenize("{a,b}c").unwrap()).unwrap();
+/// let expanded = expand_spanned(&node).unwrap();
+/// assert_eq!(expanded[1].value, "bc");
+/// assert_eq!(expanded[1].choices[0].group, 0..5);
+/// assert_eq!(expanded[1].choices[0].item, 3..4);
+/// ```
+///
+/// # Errors
+///
+/// Returns an `ExpansionError` if the expansion fails, same as [expand].
+pub fn expand_spanned(node: &crate::parser::Node) -> Result<Vec<SpannedExpansion>, ExpansionError> {
+    match node {
+        parser::Node::Text { message, .. } => Ok(vec![SpannedExpansion {
+            value: message.as_ref().to_owned(),
+            choices: vec![],
+        }]),
+        parser::Node::BraceExpansion {
+            prefix,
+            inside,
+            postfix,
+            start: _,
+            end: _,
+        } => {
+            let mut inner = vec![SpannedExpansion {
+                value: String::new(),
+                choices: vec![],
+            }];
+            for part in [prefix, inside, postfix].into_iter().flatten() {
+                let expansions = expand_spanned(part)?;
+                let mut combined = Vec::with_capacity(inner.len() * expansions.len());
+                for left in &inner {
+                    for right in &expansions {
+                        let mut choices = left.choices.clone();
+                        choices.extend(right.choices.iter().cloned());
+                        combined.push(SpannedExpansion {
+                            value: format!("{}{}", left.value, right.value),
+                            choices,
+                        });
+                    }
+                }
+                inner = combined;
+            }
+            Ok(inner)
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let mut inner = vec![];
+            for item in items {
+                for mut expansion in expand_spanned(item)? {
+                    expansion.choices.insert(
+                        0,
+                        Choice {
+                            group: node.span(),
+                            item: item.span(),
+                        },
+                    );
+                    inner.push(expansion);
+                }
+            }
+            Ok(inner)
+        }
+        parser::Node::Range { .. }
+        | parser::Node::CharRange { .. }
+        | parser::Node::AlphaRange { .. } => Ok(expand(node)?
+            .into_iter()
+            .map(|value| SpannedExpansion {
+                value,
+                choices: vec![Choice {
+                    group: node.span(),
+                    item: node.span(),
+                }],
+            })
+            .collect()),
+    }
+}
+
+/// Same functionality as [bracoxidize] but with explosive materials. This crates' all
+/// Error types implements [std::error::Error] trait. Why not get all the benefits from it?
+pub fn explode(content: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let tokens = tokenizer::tokenize(content)?;
+    let ast = parser::parse(&tokens)?;
+    let expansions = expand(&ast)?;
+    Ok(expansions)
+}
+
+/// Errors that can occur during the Brace Expansion process.
+///
+/// Like the errors of each stage it wraps, it's non-exhaustive, so a `match` on it needs a
+/// wildcard arm.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum OxidizationError {
+    TokenizationError(tokenizer::TokenizationError),
+    ParsingError(parser::ParsingError),
+    ExpansionError(ExpansionError),
+    /// Reading the content or writing the expansions failed, see [bracoxidize_lines] and
+    /// [bracoxidize_to_path].
+    IoError(std::io::ErrorKind),
+}
+
+impl std::fmt::Display for OxidizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OxidizationError::TokenizationError(error) => write!(f, "Tokenization failed: {error}"),
+            OxidizationError::ParsingError(error) => write!(f, "Parsing failed: {error}"),
+            OxidizationError::ExpansionError(error) => write!(f, "Expansion failed: {error}"),
+            OxidizationError::IoError(kind) => write!(f, "I/O failed: {kind}"),
+        }
+    }
+}
+
+impl std::error::Error for OxidizationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OxidizationError::TokenizationError(error) => Some(error),
+            OxidizationError::ParsingError(error) => Some(error),
+            OxidizationError::ExpansionError(error) => Some(error),
+            OxidizationError::IoError(_) => None,
+        }
+    }
+}
+
+impl From<tokenizer::TokenizationError> for OxidizationError {
+    fn from(error: tokenizer::TokenizationError) -> Self {
+        OxidizationError::TokenizationError(error)
+    }
+}
+
+impl From<parser::ParsingError> for OxidizationError {
+    fn from(error: parser::ParsingError) -> Self {
+        OxidizationError::ParsingError(error)
+    }
+}
+
+impl From<ExpansionError> for OxidizationError {
+    fn from(error: ExpansionError) -> Self {
+        OxidizationError::ExpansionError(error)
+    }
+}
+
+/// Renders the given error, returned for the provided content, as a diagnostic pointing at
+/// where it occurred: the line of the content, carets under the offending chars, and the
+/// message, e.g. for a command-line tool.
+///
+/// Errors without a position, e.g. [tokenizer::TokenizationError::NoBraces], are rendered as
+/// the message alone.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::{bracoxidize, render_error};
+///
+/// let content = "{a,b}}";
+/// let error = bracoxidize(content).unwrap_err();
+/// assert_eq!(
+///     render_error(content, &error),
+///     "{a,b}}\n     ^\nParsing failed: Used extra closing bracket at 5"
+/// );
+/// ```
+pub fn render_error(content: &str, err: &OxidizationError) -> String {
+    let Some(span) = error_span(err) else {
+        return err.to_string();
+    };
+    // positions are char indices, find the line holding the start of the span.
+    let mut line_start = 0;
+    for line in content.split('\n') {
+        let len = line.chars().count();
+        if span.start <= line_start + len {
+            let column = span.start - line_start;
+            // a span past the end of the line is cut, it's marked with a single caret anyway.
+            let width = span
+                .end
+                .min(line_start + len)
+                .saturating_sub(span.start)
+                .max(1);
+            return format!("{line}\n{}{}\n{err}", " ".repeat(column), "^".repeat(width));
+        }
+        line_start += len + 1;
+    }
+    err.to_string()
+}
+
+/// Returns the span of the content the given error points at, if it has a position.
+fn error_span(err: &OxidizationError) -> Option<std::ops::Range<usize>> {
+    use parser::ParsingError;
+    let position = match err {
+        OxidizationError::TokenizationError(tokenizer::TokenizationError::UnclosedQuote(i)) => *i,
+        OxidizationError::ParsingError(error) => match error {
+            ParsingError::NoTokens => return None,
+            ParsingError::OBraExpected(i)
+            | ParsingError::CBraExpected(i)
+            | ParsingError::RangeStartLimitExpected(i)
+            | ParsingError::RangeEndLimitExpected(i)
+            | ParsingError::ExpectedText(i)
+            | ParsingError::InvalidCommaUsage(i)
+            | ParsingError::ExtraCBra(i)
+            | ParsingError::ExtraOBra(i)
+            | ParsingError::NothingInBraces(i)
+            | ParsingError::RangeCantHaveText(i)
+            | ParsingError::ExtraRangeOperator(i)
+            | ParsingError::SampleCountExpected(i)
+            | ParsingError::StepExpected(i)
+            | ParsingError::MixedSeparators(i) => *i,
+        },
+        OxidizationError::ExpansionError(
+            ExpansionError::LimitExceeded { span, .. }
+            | ExpansionError::NotANumericRange(span)
+            | ExpansionError::RangeTooLarge { span, .. },
+        ) => return Some(span.clone()),
+        _ => return None,
+    };
+    Some(position..position + 1)
+}
+
+/// Bracoxidize the provided content by tokenizing, parsing, and expanding brace patterns.
+///
+/// # Arguments
+///
+/// * `content` - The input string to be processed.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the expanded brace patterns as `Vec<String>`,
+/// or an `OxidizationError` if an error occurs during the process.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::{bracoxidize, OxidizationError};
+///
+/// fn main() {
+///     let content = "foo{1..3}bar";
+///     match bracoxidize(content) {
+///         Ok(expanded) => {
+///             println!("Expanded patterns: {:?}", expanded);
+///         }
+///         Err(error) => {
+///             eprintln!("Error occurred: {:?}", error);
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns an [OxidizationError] if the content can't be tokenized, parsed, or expanded. Blank
+/// content is an error too: empty content fails with [TokenizationError::EmptyContent], while
+/// whitespace only content, like any other content without braces, fails with
+/// [TokenizationError::NoBraces]. See [ExpandOptions::passthrough_blank] to keep it instead.
+pub fn bracoxidize(content: &str) -> Result<Vec<String>, OxidizationError> {
+    bracoxidize_with(content, &ExpandOptions::default())
+}
+
+/// Bracoxidizes the provided content like [bracoxidize], and serializes the expansions into a
+/// JSON array of strings. Requires the `serde_json` feature.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::bracoxidize_json;
+///
+/// assert_eq!(bracoxidize_json("{a,\"b\"}").unwrap(), r#"["a","\"b\""]"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [bracoxidize].
+#[cfg(feature = "serde_json")]
+pub fn bracoxidize_json(content: &str) -> Result<String, OxidizationError> {
+    let expanded = bracoxidize(content)?;
+    Ok(serde_json::to_string(&expanded).expect("a list of strings always serializes"))
+}
+
+/// Returns whether the provided content has anything to expand: a comma or a range operator
+/// (`..`) directly inside a pair of matching braces.
+///
+/// Braces alone aren't enough, e.g. `{a}` has nothing to expand under bash rules. Escaped
+/// characters are skipped. The content is scanned once, so it's a cheap check to skip the
+/// whole pipeline for inert strings.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::contains_expansion;
+///
+/// assert!(contains_expansion("{a,b}"));
+/// assert!(contains_expansion("file{1..3}.txt"));
+/// assert!(!contains_expansion("{a}"));
+/// assert!(!contains_expansion("a,b"));
+/// ```
+pub fn contains_expansion(content: &str) -> bool {
+    // whether a comma or range operator was seen in each open brace, innermost last.
+    let mut opened: Vec<bool> = vec![];
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => opened.push(false),
+            // the closing brace pops its opening brace, whether the guard matches or not.
+            '}' if opened.pop() == Some(true) => return true,
+            ',' => {
+                if let Some(found) = opened.last_mut() {
+                    *found = true;
+                }
+            }
+            '.' if chars.peek() == Some(&'.') => {
+                chars.next();
+                if let Some(found) = opened.last_mut() {
+                    *found = true;
+                }
+            }
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Bracoxidize the provided content like [bracoxidize], expanding with the provided
+/// [ExpandOptions].
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::{bracoxidize_with, ExpandOptions};
+///
+/// let expanded = bracoxidize_with("{a..c}", &ExpandOptions::default()).unwrap();
+/// assert_eq!(expanded, vec!["a", "b", "c"]);
+/// ```
+pub fn bracoxidize_with(
+    content: &str,
+    options: &ExpandOptions,
+) -> Result<Vec<String>, OxidizationError> {
+    // Strip the comments, if allowed
+    let (stripped, removed);
+    let content = if options.allow_comments {
+        (stripped, removed) = tokenizer::strip_comments(content, &options.tokenizer);
+        stripped.as_str()
+    } else {
+        removed = vec![];
+        content
+    };
+    if options.passthrough_blank && content.trim().is_empty() {
+        return Ok(vec![content.to_owned()]);
+    }
+    // Tokenize the input string, its positions point past the stripped comments
+    let mut tokens =
+        tokenizer::tokenize_with(content, &options.tokenizer).map_err(|error| match error {
+            tokenizer::TokenizationError::UnclosedQuote(i) => {
+                tokenizer::TokenizationError::UnclosedQuote(tokenizer::unstrip_position(
+                    i, &removed,
+                ))
+            }
+            error => error,
+        })?;
+    tokenizer::unstrip_positions(&mut tokens, &removed);
+    // Parse the tokens into an abstract syntax tree
+    let ast = parser::parse(&tokens)?;
+    // Expand the brace patterns in the AST
+    Ok(expand_with(&ast, options)?)
+}
+
+/// Bracoxidize the provided content like [bracoxidize], quoting every expansion so it can
+/// be pasted into a POSIX shell command line as a single word.
+///
+/// Every expansion is wrapped in single quotes, and every single quote inside it is replaced
+/// with `'\''` (close the quote, an escaped quote, reopen the quote). Nothing else is changed,
+/// since no character is special between single quotes.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::bracoxidize_shell_quoted;
+///
+/// let expanded = bracoxidize_shell_quoted("{my file,it's}.txt").unwrap();
+/// assert_eq!(expanded, vec!["'my file.txt'", "'it'\\''s.txt'"]);
+/// ```
+pub fn bracoxidize_shell_quoted(content: &str) -> Result<Vec<String>, OxidizationError> {
+    Ok(bracoxidize(content)?
+        .into_iter()
+        .map(|expansion| format!("'{}'", expansion.replace('\'', "'\\''")))
+        .collect())
+}
+
+/// Bracoxidize the provided content like [bracoxidize], grouping the expansions by the value
+/// of the first brace group which isn't nested in another one.
+///
+/// Each group holds the value chosen in that brace group, and the expansions made with it, in
+/// the order [bracoxidize] produces them. A value of a nested group is part of the value, e.g.
+/// `{a{1,2},b}` groups by `a1`, `a2` and `b`, and every value of a range is a group of its own.
+/// A pattern whose first group has a single item, e.g. `x{a}y`, produces a single group.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::bracoxidize_grouped;
+///
+/// let grouped = bracoxidize_grouped("{dir1,dir2}/{a,b}").unwrap();
+/// assert_eq!(grouped[0], ("dir1".to_owned(), vec!["dir1/a".to_owned(), "dir1/b".to_owned()]));
+/// assert_eq!(grouped[1], ("dir2".to_owned(), vec!["dir2/a".to_owned(), "dir2/b".to_owned()]));
+/// ```
+pub fn bracoxidize_grouped(content: &str) -> Result<Vec<(String, Vec<String>)>, OxidizationError> {
+    let tokens = tokenizer::tokenize(content)?;
+    let ast = parser::parse(&tokens)?;
+    let expand_or_empty = |node: &Option<Box<parser::Node>>| match node {
+        Some(node) => expand(node),
+        None => Ok(vec!["".to_owned()]),
+    };
+    match &ast {
+        parser::Node::BraceExpansion {
+            prefix,
+            inside: Some(inside),
+            postfix,
+            ..
+        } => {
+            let prefixs = expand_or_empty(prefix)?;
+            let postfixs = expand_or_empty(postfix)?;
+            let mut grouped = vec![];
+            for value in expand(inside)? {
+                let mut group = vec![];
+                for prefix in &prefixs {
+                    for postfix in &postfixs {
+                        group.push(format!("{}{}{}", prefix, value, postfix));
+                    }
+                }
+                grouped.push((value, group));
+            }
+            Ok(grouped)
+        }
+        // There is no brace group to group by, every expansion is a group of its own.
+        node => Ok(expand(node)?
+            .into_iter()
+            .map(|expansion| (expansion.clone(), vec![expansion]))
+            .collect()),
+    }
+}
+
+/// A non-fatal diagnostic about a likely unintended part of a pattern, see [validate].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lint {
+    /// Span of the brace group the diagnostic is about, including both braces.
+    pub span: std::ops::Range<usize>,
+    /// Human-readable description of the issue.
+    pub message: String,
+}
+
+/// Validates the provided content without expanding it, returning the [Lint]s found in it.
+///
+/// The content is valid if it can be tokenized and parsed, the lints only point at brace
+/// groups which are valid but have nothing to expand, so are likely typos:
+/// - A brace group with a single item, e.g. `{a}`.
+/// - A range with a single value, e.g. `{1..1}`.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::validate;
+///
+/// let lints = validate("x{a}{1,2}").unwrap();
+/// assert_eq!(lints.len(), 1);
+/// assert_eq!(lints[0].span, 1..4);
+/// ```
+pub fn validate(content: &str) -> Result<Vec<Lint>, OxidizationError> {
+    let tokens = tokenizer::tokenize(content)?;
+    let ast = parser::parse(&tokens)?;
+    let mut lints = vec![];
+    lint_node(&ast, &mut lints)?;
+    Ok(lints)
+}
+
+/// Appends the lints of `node` and its items to `lints`, see [validate].
+fn lint_node(node: &parser::Node, lints: &mut Vec<Lint>) -> Result<(), ExpansionError> {
+    match node {
+        parser::Node::Text { .. } => (),
+        parser::Node::BraceExpansion { .. } => {
+            for (part, is_group) in chain_groups(node) {
+                match part {
+                    // a group with a single item is parsed as the text of the item.
+                    parser::Node::Text { .. } if is_group => {
+                        let span = part.span();
+                        lints.push(Lint {
+                            span: span.start.saturating_sub(1)..span.end + 1,
+                            message: "brace group with a single item has nothing to expand, \
+                                      is a comma missing?"
+                                .to_owned(),
+                        })
+                    }
+                    parser::Node::Text { .. } => (),
+                    part => lint_node(part, lints)?,
+                }
+            }
+        }
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            for item in items {
+                lint_node(item, lints)?;
+            }
+        }
+        parser::Node::Range { .. }
+        | parser::Node::CharRange { .. }
+        | parser::Node::AlphaRange { .. } => {
+            if count(node)? == 1 {
+                lints.push(Lint {
+                    span: node.span(),
+                    message: "range with a single value has nothing to expand".to_owned(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Explains how the provided content expands, without expanding it.
+///
+/// Returns a tree of the parsed content, a line per node, nested items indented by two
+/// spaces. Each line names the node, its span in the content, and how many combinations it
+/// produces. Adjacent texts and brace groups are listed as the parts of a sequence.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::explain;
+///
+/// let explanation = explain("x{a,b}").unwrap();
+/// assert_eq!(
+///     explanation,
+///     "sequence at 0..6, 2 combinations\n  text \"x\" at 0..1\n  group at 1..6, 2 combinations\n    text \"a\" at 2..3\n    text \"b\" at 4..5\n"
+/// );
+/// ```
+pub fn explain(content: &str) -> Result<String, OxidizationError> {
+    let tokens = tokenizer::tokenize(content)?;
+    let ast = parser::parse(&tokens)?;
+    let mut explanation = String::new();
+    explain_node(&ast, 0, &mut explanation)?;
+    Ok(explanation)
+}
+
+/// Appends the explanation of `node` and its items to `explanation`, see [explain].
+fn explain_node(
+    node: &parser::Node,
+    depth: usize,
+    explanation: &mut String,
+) -> Result<(), ExpansionError> {
+    let indent = "  ".repeat(depth);
+    let span = node.span();
+    let count = count(node)?;
+    match node {
+        parser::Node::Text { message, .. } => {
+            explanation.push_str(&format!(
+                "{indent}text {message:?} at {}..{}\n",
+                span.start, span.end
+            ));
+        }
+        parser::Node::BraceExpansion { .. } => match chain_parts(node).as_slice() {
+            [part] => explain_node(part, depth, explanation)?,
+            parts => {
+                explanation.push_str(&format!(
+                    "{indent}sequence at {}..{}, {count} combinations\n",
+                    span.start, span.end
+                ));
+                for part in parts {
+                    explain_node(part, depth + 1, explanation)?;
+                }
+            }
+        },
+        parser::Node::Collection { items, .. } | parser::Node::Alternation { items, .. } => {
+            let kind = match node {
+                parser::Node::Alternation { .. } => "alternation",
+                _ => "group",
+            };
+            explanation.push_str(&format!(
+                "{indent}{kind} at {}..{}, {count} combinations\n",
+                span.start, span.end
+            ));
+            for item in items {
+                explain_node(item, depth + 1, explanation)?;
+            }
+        }
+        parser::Node::Range {
+            from,
+            to,
+            inclusive,
+            sample_count,
+            step,
+            ..
+        } => {
+            let exclusive = if *inclusive { "" } else { " (exclusive)" };
+            let sample = match (sample_count, step) {
+                (Some(n), _) => format!(" sampling {n}"),
+                (None, Some(step)) => format!(" by {step}"),
+                (None, None) => String::new(),
+            };
+            explanation.push_str(&format!(
+                "{indent}range from {from} to {to}{exclusive}{sample} at {}..{}, {count} combinations\n",
+                span.start, span.end
+            ));
+        }
+        parser::Node::CharRange {
+            from,
+            to,
+            inclusive,
+            case,
+            ..
+        } => {
+            let exclusive = if *inclusive { "" } else { " (exclusive)" };
+            let case = match case {
+                Some(parser::Case::Upper) => " in uppercase",
+                Some(parser::Case::Lower) => " in lowercase",
+                None => "",
+            };
+            explanation.push_str(&format!(
+                "{indent}char range from {from:?} to {to:?}{exclusive}{case} at {}..{}, {count} combinations\n",
+                span.start, span.end
+            ));
+        }
+        parser::Node::AlphaRange {
+            from,
+            to,
+            inclusive,
+            ..
+        } => {
+            let exclusive = if *inclusive { "" } else { " (exclusive)" };
+            explanation.push_str(&format!(
+                "{indent}alphabetic range from {from:?} to {to:?}{exclusive} at {}..{}, {count} combinations\n",
+                span.start, span.end
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Bracoxidizes every line read from `reader`, one line at a time.
+///
+/// Each item of the returned iterator holds the expansion of one line, or the error which
+/// occurred while reading or expanding that line. Reading errors are reported as
+/// [OxidizationError::IoError]. When `skip_comments` is `true`, blank lines and lines
+/// starting with `#` are skipped instead of being expanded.
+///
+/// # Examples
+///
+/// ```rust
+/// use bracoxide::bracoxidize_lines;
+///
+/// let content = "# directories\na{1..2}\n\n{x,y}z\n";
+/// let expanded: Vec<_> = bracoxidize_lines(content.as_bytes(), true).collect();
+/// assert_eq!(
+///     expanded,
+///     vec![Ok(vec!["a1".to_owned(), "a2".to_owned()]), Ok(vec!["xz".to_owned(), "yz".to_owned()])]
+/// );
+/// ```
+pub fn bracoxidize_lines<R: std::io::BufRead>(
+    reader: R,
+    skip_comments: bool,
+) -> impl Iterator<Item = Result<Vec<String>, OxidizationError>> {
+    reader.lines().filter_map(move |line| match line {
+        Ok(line) if skip_comments && (line.trim().is_empty() || line.starts_with('#')) => None,
+        Ok(line) => Some(bracoxidize(&line)),
+        Err(error) => Some(Err(OxidizationError::IoError(error.kind()))),
+    })
+}
+
+/// Bracoxidizes the provided content like [bracoxidize], writing every expansion on a line of
+/// its own to the file at `out` instead of collecting them, and returns the count of written
+/// expansions. The file is created, or truncated if it exists.
+///
+/// The expansions are written like [expand_to_writer_progress] does, one by one. So
+/// expansions way larger than the memory, e.g. billion-line manifests, can still be
+/// materialized.
+///
+/// # Errors
+///
+/// Returns the same errors as [bracoxidize] and [expand_to_writer_progress], or
+/// [OxidizationError::IoError] if creating or writing the file fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use bracoxide::bracoxidize_to_path;
+///
+/// let count = bracoxidize_to_path("{a..z}{0..9999}", "manifest.txt".as_ref()).unwrap();
+/// assert_eq!(count, 260_000);
+/// ```
+pub fn bracoxidize_to_path(
+    content: &str,
+    out: &std::path::Path,
+) -> Result<usize, OxidizationError> {
+    use std::io::Write;
+    let tokens = tokenizer::tokenize(content)?;
+    let ast = parser::parse(&tokens)?;
+    let io = |error: std::io::Error| OxidizationError::IoError(error.kind());
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(out).map_err(io)?);
+    let count = expand_to_writer_progress(&ast, "\n", &mut writer, |_, _| ())?;
+    writer.flush().map_err(io)?;
+    Ok(count)
+}
+
+/// The count of expansions [expand_to_writer_progress] writes between two progress reports.
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Expands the given parsed node into the given writer, each expansion followed by `sep`,
+/// and returns the count of written expansions. Every 10,000 expansions, and once all are
+/// written, `progress` is called with the count of written expansions and the total from
+/// [count], e.g. to render a progress bar.
+///
+/// Only the brace groups and texts the node is made of are expanded in memory, their
+/// combinations are written one by one, in many small writes. So the writer is better
+/// buffered, e.g. with a [std::io::BufWriter], which is flushed by the caller.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::parser::parse;
+/// use bracoxide::tokenizer::tokenize;
+/// use bracoxide::expand_to_writer_progress;
+///
+/// let node = parse(&tokenize("{a,b}{1..2}").unwrap()).unwrap();
+/// let mut out = vec![];
+/// let mut reports = vec![];
+/// let written = expand_to_writer_progress(&node, "\n", &mut out, |done, total| {
+///     reports.push((done, total))
+/// });
+/// assert_eq!(written, Ok(4));
+/// assert_eq!(out, b"a1\na2\nb1\nb2\n");
+/// assert_eq!(reports, vec![(4, 4)]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [count] and [expand], or [OxidizationError::IoError] if writing
+/// fails.
+pub fn expand_to_writer_progress<W, F>(
+    node: &crate::parser::Node,
+    sep: &str,
+    mut writer: W,
+    mut progress: F,
+) -> Result<usize, OxidizationError>
+where
+    W: std::io::Write,
+    F: FnMut(usize, usize),
+{
+    let total = count(node)?;
+    let mut combinations = Combinations::expand(node, &ExpandOptions::default())?;
+    let io = |error: std::io::Error| OxidizationError::IoError(error.kind());
+    let mut done = 0_usize;
+    while combinations.advance() {
+        for value in combinations.values() {
+            writer.write_all(value.as_bytes()).map_err(io)?;
+        }
+        writer.write_all(sep.as_bytes()).map_err(io)?;
+        done += 1;
+        if done.is_multiple_of(PROGRESS_INTERVAL) && done < total {
+            progress(done, total);
+        }
+    }
+    progress(done, total);
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::parser::Node;
+    use super::*;
+
+    /// Tokenizes and parses the given content, which must be valid.
+    fn parse(content: &str) -> Node {
+        parser::parse(&tokenizer::tokenize(content).unwrap()).unwrap()
+    }
+
+    /// Asserts that `f` expands each of the contents like [expand].
+    fn assert_expands_like<T: ToString>(
+        contents: &[&str],
+        f: impl Fn(&Node) -> Result<Vec<T>, ExpansionError>,
+    ) {
+        for content in contents {
+            let node = parse(content);
+            let expanded: Vec<String> = f(&node).unwrap().iter().map(T::to_string).collect();
+            assert_eq!(expanded, expand(&node).unwrap(), "{content}");
+        }
+    }
+
+    #[test]
+    fn test_expand_complex() {
+        assert_eq!(
+            expand(&Node::BraceExpansion {
+                prefix: Some(Box::new(Node::Text {
+                    message: Arc::new("A".into()),
+                    start: 0,
+                    end: 1
+                })),
+                inside: Some(Box::new(Node::Collection {
+                    items: vec![
+                        Node::Text {
+                            message: Arc::new("B".into()),
+                            start: 2,
+                            end: 3
+                        },
+                        Node::BraceExpansion {
+                            prefix: Some(Box::new(Node::Text {
+                                message: Arc::new("C".into()),
+                                start: 4,
+                                end: 5
+                            })),
+                            inside: Some(Box::new(Node::Collection {
+                                items: vec![
+                                    Node::Text {
+                                        message: Arc::new("D".into()),
+                                        start: 6,
+                                        end: 7
+                                    },
+                                    Node::Text {
+                                        message: Arc::new("E".into()),
+                                        start: 8,
+                                        end: 9
+                                    },
+                                ],
+                                start: 5,
+                                end: 9
+                            })),
+                            postfix: Some(Box::new(Node::Text {
+                                message: Arc::new("F".into()),
+                                start: 10,
+                                end: 11
+                            })),
+                            start: 4,
+                            end: 10,
+                        },
+                        Node::Text {
+                            message: Arc::new("G".into()),
+                            start: 12,
+                            end: 13
+                        }
+                    ],
+                    start: 1,
+                    end: 13
+                })),
+                postfix: Some(Box::new(Node::BraceExpansion {
+                    prefix: Some(Box::new(Node::Text {
+                        message: Arc::new("H".into()),
+                        start: 14,
+                        end: 15
+                    })),
+                    inside: Some(Box::new(Node::Collection {
+                        items: vec![
+                            Node::Text {
+                                message: Arc::new("J".into()),
+                                start: 16,
+                                end: 17
+                            },
+                            Node::Text {
+                                message: Arc::new("K".into()),
+                                start: 18,
+                                end: 19
+                            },
+                        ],
+                        start: 15,
+                        end: 19
+                    })),
+                    postfix: Some(Box::new(Node::BraceExpansion {
+                        prefix: Some(Box::new(Node::Text {
+                            message: Arc::new("L".into()),
+                            start: 20,
+                            end: 21
+                        })),
+                        inside: Some(Box::new(Node::Range {
+                            from: Arc::new("3".into()),
+                            to: Arc::new("5".into()),
+                            inclusive: true,
+                            sample_count: None,
+                            step: None,
+                            start: 21,
+                            end: 26
+                        })),
+                        postfix: None,
+                        start: 20,
+                        end: 26
+                    })),
+                    start: 14,
+                    end: 26
+                })),
+                start: 0,
+                end: 26
+            }),
+            Ok(vec![
+                "ABHJL3".to_owned(),
+                "ABHJL4".to_owned(),
+                "ABHJL5".to_owned(),
+                "ABHKL3".to_owned(),
+                "ABHKL4".to_owned(),
+                "ABHKL5".to_owned(),
+                "ACDFHJL3".to_owned(),
+                "ACDFHJL4".to_owned(),
+                "ACDFHJL5".to_owned(),
+                "ACDFHKL3".to_owned(),
+                "ACDFHKL4".to_owned(),
+                "ACDFHKL5".to_owned(),
+                "ACEFHJL3".to_owned(),
+                "ACEFHJL4".to_owned(),
+                "ACEFHJL5".to_owned(),
+                "ACEFHKL3".to_owned(),
+                "ACEFHKL4".to_owned(),
+                "ACEFHKL5".to_owned(),
+                "AGHJL3".to_owned(),
+                "AGHJL4".to_owned(),
+                "AGHJL5".to_owned(),
+                "AGHKL3".to_owned(),
+                "AGHKL4".to_owned(),
+                "AGHKL5".to_owned(),
+            ])
+        )
+    }
+    #[test]
+    fn test_expand_complex_bracoxidize() {
+        assert_eq!(
+            bracoxidize("A{B,C{D,E}F,G}H{J,K}L{3..5}"),
+            Ok(vec![
+                "ABHJL3".to_owned(),
+                "ABHJL4".to_owned(),
+                "ABHJL5".to_owned(),
+                "ABHKL3".to_owned(),
+                "ABHKL4".to_owned(),
+                "ABHKL5".to_owned(),
+                "ACDFHJL3".to_owned(),
+                "ACDFHJL4".to_owned(),
+                "ACDFHJL5".to_owned(),
+                "ACDFHKL3".to_owned(),
+                "ACDFHKL4".to_owned(),
+                "ACDFHKL5".to_owned(),
+                "ACEFHJL3".to_owned(),
+                "ACEFHJL4".to_owned(),
+                "ACEFHJL5".to_owned(),
+                "ACEFHKL3".to_owned(),
+                "ACEFHKL4".to_owned(),
+                "ACEFHKL5".to_owned(),
+                "AGHJL3".to_owned(),
+                "AGHJL4".to_owned(),
+                "AGHJL5".to_owned(),
+                "AGHKL3".to_owned(),
+                "AGHKL4".to_owned(),
+                "AGHKL5".to_owned(),
+            ])
+        )
+    }
+
+    #[test]
+    fn test_empty_prefix_and_postfix() {
+        assert_eq!(
+            bracoxidize("{a,b}c"),
+            Ok(vec!["ac".to_owned(), "bc".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("c{a,b}"),
+            Ok(vec!["ca".to_owned(), "cb".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_exclusive_range() {
+        assert_eq!(
+            bracoxidize("{1..<5}"),
+            Ok(vec![
+                "1".to_owned(),
+                "2".to_owned(),
+                "3".to_owned(),
+                "4".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{1..5}"),
+            Ok(vec![
+                "1".to_owned(),
+                "2".to_owned(),
+                "3".to_owned(),
+                "4".to_owned(),
+                "5".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{1..<}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeEndLimitExpected(2)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_only_commas() {
+        assert_eq!(bracoxidize("{,}"), Ok(vec![String::new(); 2]));
+        assert_eq!(bracoxidize("{,,}"), Ok(vec![String::new(); 3]));
+        assert_eq!(bracoxidize("{,,,}"), Ok(vec![String::new(); 4]));
+        assert_eq!(bracoxidize("{,,,,}"), Ok(vec![String::new(); 5]));
+        assert_eq!(
+            bracoxidize("a{,}b"),
+            Ok(vec!["ab".to_owned(), "ab".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_leading_empty_item() {
+        let expand = |content| bracoxidize(content).unwrap();
+        assert_eq!(expand("A{,B,C}D"), ["AD", "ABD", "ACD"]);
+        assert_eq!(expand("file{,.bak}"), ["file", "file.bak"]);
+        assert_eq!(expand("{,x}{,y}"), ["", "y", "x", "xy"]);
+        assert_eq!(expand("A{,{1..2}}"), ["A", "A1", "A2"]);
+    }
+
+    #[test]
+    fn test_expand_cloned_ast() {
+        let ast = parse("A{B,C{D,E}F}G{1..2}");
+        let cloned = ast.clone();
+        assert_eq!(cloned, ast);
+        assert_eq!(expand(&cloned), expand(&ast));
+        assert_eq!(expand(&cloned).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_char_range() {
+        assert_eq!(
+            bracoxidize("{a..e}"),
+            Ok(vec![
+                "a".to_owned(),
+                "b".to_owned(),
+                "c".to_owned(),
+                "d".to_owned(),
+                "e".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{c..a}"),
+            Ok(vec!["c".to_owned(), "b".to_owned(), "a".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{a..<c}"),
+            Ok(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{1..a}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(4)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{ab..c}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_empty_braces() {
+        assert_eq!(
+            bracoxidize("a{}b"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::NothingInBraces(1)
+            ))
+        );
+        let options = ExpandOptions::default()
+            .tokenizer(tokenizer::TokenizerOptions::default().empty_braces(true));
+        assert_eq!(
+            bracoxidize_with("a{}b", &options),
+            Ok(vec!["ab".to_owned()])
+        );
+        assert_eq!(bracoxidize_with("{}", &options), Ok(vec!["".to_owned()]));
+        assert_eq!(
+            bracoxidize_with("{a,{}}x", &options),
+            Ok(vec!["ax".to_owned(), "x".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_contains_expansion() {
+        for content in ["{a,b}", "{1..3}", "x{a..<c}y", "{a{b,c}", "{{a},b}", "{,}"] {
+            assert!(contains_expansion(content), "{content}");
+        }
+        for content in [
+            "", "abc", "{a}", "a,b", "{a,b", "a,b}", "}a,b{", "\\{a,b}", "{a\\,b}", "{a,{b}",
+            "{a.b}", "1..3",
+        ] {
+            assert!(!contains_expansion(content), "{content}");
+        }
+    }
+
+    #[test]
+    fn test_flat_collection() {
+        // bounding the output bytes skips the fast path for flat collections.
+        let generic = ExpandOptions::default().max_output_bytes(usize::MAX);
+        for content in [
+            "{a,b}",
+            "x{a,b}",
+            "{a,b}y",
+            "x{a,,b}y",
+            "{a,b}{c,d}",
+            "x{a,b{1,2}}y",
+        ] {
+            let node = parse(content);
+            assert_eq!(expand(&node), expand_with(&node, &generic), "{content}");
+        }
+    }
+
+    #[test]
+    fn test_empty_trailing_item() {
+        assert_eq!(
+            bracoxidize("{a,b,}"),
+            Ok(vec!["a".to_owned(), "b".to_owned(), "".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("x{a,b,}y"),
+            Ok(vec!["xay".to_owned(), "xby".to_owned(), "xy".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_mixed_collection() {
+        assert_eq!(
+            bracoxidize("{1..3,x,5..6}"),
+            Ok(vec![
+                "1".to_owned(),
+                "2".to_owned(),
+                "3".to_owned(),
+                "x".to_owned(),
+                "5".to_owned(),
+                "6".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("a{x,b..c,y{1..<3}}"),
+            Ok(vec![
+                "ax".to_owned(),
+                "ab".to_owned(),
+                "ac".to_owned(),
+                "ay1".to_owned(),
+                "ay2".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{x,1..}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeEndLimitExpected(4)
+            ))
+        );
+        let expand = |content| bracoxidize(content).unwrap().join(" ");
+        assert_eq!(expand("{0,2..5,8}"), "0 2 3 4 5 8");
+        assert_eq!(expand("{0,2..8..3,9}"), "0 2 5 8 9");
+        assert_eq!(expand("{0,2..<5,-1..-2}"), "0 2 3 4 -1 -2");
+        // a malformed item fails at its range operator, not at the whole group.
+        assert_eq!(
+            bracoxidize("{0,2..,8}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeEndLimitExpected(4)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{0,..5,8}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeStartLimitExpected(3)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_escaped_range() {
+        assert_eq!(bracoxidize("{\\1..\\3}"), Ok(vec!["1..3".to_owned()]));
+        assert_eq!(bracoxidize("x{\\1..<\\3}"), Ok(vec!["x1..<3".to_owned()]));
+        assert_eq!(
+            bracoxidize("{1..3}"),
+            Ok(vec!["1".to_owned(), "2".to_owned(), "3".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("1\\a{b,c}"),
+            Ok(vec!["1ab".to_owned(), "1ac".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces_in_items() {
+        let expanded = |content| bracoxidize(content).unwrap();
+        assert_eq!(expanded("{a\\{x\\},b}"), ["a{x}", "b"]);
+        // escaped braces don't have to be matched.
+        assert_eq!(expanded("{a\\{x,b\\}}"), ["a{x", "b}"]);
+        assert_eq!(expanded("p\\{{a\\,b,c}\\}"), ["p{a,b}", "p{c}"]);
+        assert_eq!(expanded("{\\},\\{}"), ["}", "{"]);
+    }
+
+    #[test]
+    fn test_char_range_surrogates() {
+        // the surrogates between U+D7FF and U+E000 aren't chars, so they are skipped.
+        assert_eq!(
+            bracoxidize("{\u{D7FE}..\u{E001}}"),
+            Ok(vec![
+                "\u{D7FE}".to_owned(),
+                "\u{D7FF}".to_owned(),
+                "\u{E000}".to_owned(),
+                "\u{E001}".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("é{\u{E000}..<\u{D7FF}}"),
+            Ok(vec!["é\u{E000}".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_char_range_count() {
+        assert_eq!(count(&parse("{\u{D7FE}..\u{E001}}")), Ok(4));
+        assert_eq!(count(&parse("{\u{E000}..<\u{D7FF}}")), Ok(1));
+        assert_eq!(count(&parse("{b..<b}")), Ok(0));
+        // counted without producing the 1_112_032 values of each range.
+        let huge = format!("{{{}}}", vec!["{ ..\u{10FFFF}}"; 1000].join(","));
+        assert_eq!(count(&parse(&huge)), Ok(1_112_032_000));
+        assert_eq!(
+            expand_with(&parse(&huge), &ExpandOptions::default().limit(1_000_000)),
+            Err(ExpansionError::LimitExceeded {
+                limit: 1_000_000,
+                span: 1..7
+            })
+        );
+        let alnum = ExpandOptions::default().char_range_mode(CharRangeMode::AlnumOnly);
+        assert_eq!(
+            expand_with(&parse("{Z..a}"), &alnum.clone().max_range_len(1)),
+            Err(ExpansionError::RangeTooLarge { max: 1, span: 0..6 })
+        );
+        assert_eq!(
+            expand_with(&parse("{Z..a}"), &alnum.max_range_len(2))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_alpha_range() {
+        assert_eq!(
+            bracoxidize("{aa..ad}"),
+            Ok(vec![
+                "aa".to_owned(),
+                "ab".to_owned(),
+                "ac".to_owned(),
+                "ad".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{ay..bb}"),
+            Ok(vec![
+                "ay".to_owned(),
+                "az".to_owned(),
+                "ba".to_owned(),
+                "bb".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{BA..<AY}"),
+            Ok(vec!["BA".to_owned(), "AZ".to_owned()])
+        );
+        assert_eq!(bracoxidize("{zz..zz}"), Ok(vec!["zz".to_owned()]));
+        assert_eq!(bracoxidize("x{ab..<ab}"), Ok(vec![]));
+        let node = parse("{aa..zz}");
+        assert_eq!(count(&node), Ok(676));
+        assert_eq!(
+            bracoxidize("{aa..Ab}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(1)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{az..baa}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_char_range_mode() {
+        let raw = ExpandOptions::default();
+        assert_eq!(
+            bracoxidize_with("{Z..a}", &raw),
+            Ok(vec![
+                "Z".to_owned(),
+                "[".to_owned(),
+                "\\".to_owned(),
+                "]".to_owned(),
+                "^".to_owned(),
+                "_".to_owned(),
+                "`".to_owned(),
+                "a".to_owned()
+            ])
+        );
+        let alnum = ExpandOptions::default().char_range_mode(CharRangeMode::AlnumOnly);
+        let expanded = bracoxidize_with("{A..z}", &alnum).unwrap();
+        assert_eq!(expanded.len(), 52);
+        assert!(expanded
+            .iter()
+            .all(|c| c.chars().all(char::is_alphanumeric)));
+        assert_eq!(
+            bracoxidize_with("{Z..a}", &alnum),
+            Ok(vec!["Z".to_owned(), "a".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_expand_spanned() {
+        let ast = parse("{a,b}{1..2}");
+        let choice = |group, item| Choice { group, item };
+        let expanded = expand_spanned(&ast).unwrap();
+        assert_eq!(
+            expanded,
+            vec![
+                SpannedExpansion {
+                    value: "a1".to_owned(),
+                    choices: vec![choice(0..5, 1..2), choice(5..11, 5..11)]
+                },
+                SpannedExpansion {
+                    value: "a2".to_owned(),
+                    choices: vec![choice(0..5, 1..2), choice(5..11, 5..11)]
+                },
+                SpannedExpansion {
+                    value: "b1".to_owned(),
+                    choices: vec![choice(0..5, 3..4), choice(5..11, 5..11)]
+                },
+                SpannedExpansion {
+                    value: "b2".to_owned(),
+                    choices: vec![choice(0..5, 3..4), choice(5..11, 5..11)]
+                },
+            ]
+        );
+        let values: Vec<String> = expanded.into_iter().map(|e| e.value).collect();
+        assert_eq!(Ok(values), expand(&ast));
+    }
+
+    #[test]
+    fn test_bracoxidize_lines() {
+        let content = "a{1..2}\r\n# comment\n\n{x,y}";
+        assert_eq!(
+            bracoxidize_lines(content.as_bytes(), false).collect::<Vec<_>>(),
+            vec![
+                Ok(vec!["a1".to_owned(), "a2".to_owned()]),
+                Err(OxidizationError::TokenizationError(
+                    tokenizer::TokenizationError::NoBraces
+                )),
+                Err(OxidizationError::TokenizationError(
+                    tokenizer::TokenizationError::EmptyContent
+                )),
+                Ok(vec!["x".to_owned(), "y".to_owned()]),
+            ]
+        );
+        assert_eq!(
+            bracoxidize_lines(content.as_bytes(), true).collect::<Vec<_>>(),
+            vec![
+                Ok(vec!["a1".to_owned(), "a2".to_owned()]),
+                Ok(vec!["x".to_owned(), "y".to_owned()]),
+            ]
+        );
+        let invalid_utf8: &[u8] = &[b'{', 0xff, b'}'];
+        assert_eq!(
+            bracoxidize_lines(invalid_utf8, true).collect::<Vec<_>>(),
+            vec![Err(OxidizationError::IoError(
+                std::io::ErrorKind::InvalidData
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        assert_eq!(
+            bracoxidize("{a,b}\\\n{c,d}"),
+            Ok(vec![
+                "ac".to_owned(),
+                "ad".to_owned(),
+                "bc".to_owned(),
+                "bd".to_owned()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        let limited = |content: &str, limit| {
+            let ast = parse(content);
+            expand_with(&ast, &ExpandOptions::default().limit(limit))
+        };
+        assert_eq!(limited("{a,b}{1..3}", 6).unwrap().len(), 6);
+        // 2 * 3 fits, the last group pushes it to 12.
+        assert_eq!(
+            limited("{a,b}{1..3}x{y,z}", 10),
+            Err(ExpansionError::LimitExceeded {
+                limit: 10,
+                span: 12..17
+            })
+        );
+        assert_eq!(
+            limited("{a,b}{1..30}", 10),
+            Err(ExpansionError::LimitExceeded {
+                limit: 10,
+                span: 5..12
+            })
+        );
+        assert_eq!(
+            limited("{a,b{c,d,e}}", 3),
+            Err(ExpansionError::LimitExceeded {
+                limit: 3,
+                span: 0..12
+            })
+        );
+        assert_eq!(
+            limited("{0..18446744073709551615}", usize::MAX),
+            Err(ExpansionError::LimitExceeded {
+                limit: usize::MAX,
+                span: 0..25
+            })
+        );
+    }
+
+    #[test]
+    fn test_expander() {
+        let mut expander = Expander::default();
+        for content in ["{a,b{1..3}}x{y,z}", "{a..c}", "{1..<1}", "{,}", "a{b,c}"] {
+            let node = parse(content);
+            let expected = expand(&node).unwrap();
+            assert_eq!(expander.expand(&node), Ok(expected.as_slice()), "{content}");
+        }
+        let mut expander = Expander::new(ExpandOptions::default().limit(3));
+        let node = parse("{a,b}{1,2}");
+        assert_eq!(
+            expander.expand(&node),
+            Err(ExpansionError::LimitExceeded {
+                limit: 3,
+                span: 5..10
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_at_most() {
+        let count_at_most = |content, max| {
+            let node = parse(content);
+            super::count_at_most(&node, max)
+        };
+        assert_eq!(count_at_most("{a,b}{1..3}", 100), Ok(CountResult::Exact(6)));
+        assert_eq!(count_at_most("{a,b}{1..3}", 6), Ok(CountResult::Exact(6)));
+        assert_eq!(count_at_most("{a,b}{1..3}", 5), Ok(CountResult::Exceeded));
+        // the count is (2^64)^3, which overflows even a u128.
+        let huge = "{1..18446744073709551615}".repeat(3);
+        assert_eq!(count_at_most(&huge, 1000), Ok(CountResult::Exceeded));
+        assert_eq!(count_at_most(&huge, usize::MAX), Ok(CountResult::Exceeded));
+        assert_eq!(
+            count_at_most("{1..18446744073709551616}", 10),
+            Err(ExpansionError::NumConversionFailed(
+                "18446744073709551616".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expand_numeric() {
+        let expand_numeric = |content| {
+            let node = parse(content);
+            super::expand_numeric(&node)
+        };
+        assert_eq!(expand_numeric("{1..5}"), Ok(vec![1, 2, 3, 4, 5]));
+        assert_eq!(expand_numeric("{3..<-1}"), Ok(vec![3, 2, 1, 0]));
+        assert_eq!(expand_numeric("{0..10:~3}"), Ok(vec![0, 5, 10]));
+        assert_eq!(
+            expand_numeric("{a,b}"),
+            Err(ExpansionError::NotANumericRange(0..5))
+        );
+        assert_eq!(
+            expand_numeric("x{1..5}"),
+            Err(ExpansionError::NotANumericRange(0..7))
+        );
+        assert_eq!(
+            expand_numeric("{9223372036854775807..9223372036854775808}"),
+            Err(ExpansionError::NumConversionFailed(
+                "9223372036854775808".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pattern() {
+        for content in [
+            "{a}",
+            "{a,b}{1..3}",
+            "x{a,{b,c}}y{1..10..3}",
+            "{1..<1}",
+            "{a..e}{Z..b}",
+        ] {
+            let pattern = Pattern::parse(content).unwrap();
+            let expanded = pattern.expand().unwrap();
+            assert_eq!(pattern.count(), Ok(expanded.len()));
+            assert_eq!(pattern.count.get(), Some(&expanded.len()));
+            assert_eq!(expanded, bracoxidize(content).unwrap());
+        }
+        let pattern = Pattern::parse("a{b,c}{1..3}").unwrap();
+        let options = ExpandOptions::default().limit(5);
+        assert_eq!(
+            pattern.expand_with(&options),
+            Err(ExpansionError::LimitExceeded {
+                limit: 5,
+                span: 6..12
+            })
+        );
+        let options = ExpandOptions::default()
+            .limit(12)
+            .char_range_mode(CharRangeMode::AlnumOnly);
+        let pattern = Pattern::parse("{Y..b}{1,2,3}").unwrap();
+        assert_eq!(pattern.count(), Ok(30));
+        assert_eq!(pattern.expand_with(&options).unwrap().len(), 12);
+        assert!(pattern.expand_with(&options.limit(11)).is_err());
+        let pattern = Pattern::try_from("x{a,b}").unwrap();
+        assert_eq!(pattern.node(), Pattern::parse("x{a,b}").unwrap().node());
+        assert_eq!(pattern.node(), "x{a,b}".parse::<Pattern>().unwrap().node());
+        assert_eq!(
+            Pattern::try_from("{a").map(|pattern| pattern.count()),
+            Err(OxidizationError::TokenizationError(
+                TokenizationError::FormatNotSupported
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expand_filter() {
+        let node = parse("{1..100}");
+        let expanded = expand_filter(&node, |n| n.parse::<u32>().unwrap() % 7 == 0).unwrap();
+        let expected: Vec<String> = (7..=100).step_by(7).map(|n| n.to_string()).collect();
+        assert_eq!(expanded, expected);
+        assert_expands_like(&["{a,b}{1..3}{x,y}", "{a,b}{1..<1}"], |node| {
+            expand_filter(node, |_| true)
+        });
+        assert_eq!(expand_filter(&parse("{a,b}{1..3}"), |_| false), Ok(vec![]));
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn test_bracoxidize_json() {
+        let content = "x{\"q\",\\\\,tab\t,é,\u{1}}";
+        let json = bracoxidize_json(content).unwrap();
+        let parsed: Vec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, bracoxidize(content).unwrap());
+        assert_eq!(parsed, ["x\"q\"", "x\\", "xtab\t", "xé", "x\u{1}"]);
+        assert_eq!(
+            bracoxidize_json("{a,b"),
+            Err(OxidizationError::TokenizationError(
+                tokenizer::TokenizationError::FormatNotSupported
+            ))
+        );
+    }
+
+    #[test]
+    fn test_max_range_len() {
+        let options = ExpandOptions::default().max_range_len(100);
+        assert_eq!(
+            bracoxidize_with("x{0..9999}", &options),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::RangeTooLarge {
+                    max: 100,
+                    span: 1..10
+                }
+            ))
+        );
+        // the total count is way over the maximum, but every range is within it.
+        assert_eq!(
+            bracoxidize_with("{1..100}{1..100}", &options).map(|e| e.len()),
+            Ok(10_000)
+        );
+        assert_eq!(
+            bracoxidize_with("{0..99..2}{a..z}", &options).map(|e| e.len()),
+            Ok(50 * 26)
+        );
+        assert_eq!(
+            bracoxidize_with("{aa..zz}", &options),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::RangeTooLarge {
+                    max: 100,
+                    span: 0..8
+                }
+            ))
+        );
+        let options = options.max_range_len(3);
+        assert!(bracoxidize_with("{a..c}{1..3}", &options).is_ok());
+        assert!(bracoxidize_with("{a..d}", &options).is_err());
+        assert!(bracoxidize_with("{0..100:~3}", &options).is_ok());
+        // the range is checked on its own, before the total count is.
+        let options = options.limit(2);
+        assert_eq!(
+            bracoxidize_with("{a,b}{1..9}", &options),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::RangeTooLarge {
+                    max: 3,
+                    span: 5..11
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_blank_content() {
+        let error = |error| Err(OxidizationError::TokenizationError(error));
+        assert_eq!(bracoxidize(""), error(TokenizationError::EmptyContent));
+        assert_eq!(bracoxidize("   "), error(TokenizationError::NoBraces));
+        assert_eq!(bracoxidize(" \t\n"), error(TokenizationError::NoBraces));
+        let options = ExpandOptions::default().passthrough_blank(true);
+        assert_eq!(bracoxidize_with("", &options), Ok(vec!["".to_owned()]));
+        assert_eq!(
+            bracoxidize_with("   ", &options),
+            Ok(vec!["   ".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with(" \t\n", &options),
+            Ok(vec![" \t\n".to_owned()])
+        );
+        // content which isn't blank is still expanded, or fails without braces.
+        assert_eq!(
+            bracoxidize_with(" {a,b}", &options),
+            Ok(vec![" a".to_owned(), " b".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with(" a ", &options),
+            error(TokenizationError::NoBraces)
+        );
+        let options = options.allow_comments(true);
+        assert_eq!(
+            bracoxidize_with("  # a{b,c}", &options),
+            Ok(vec!["  ".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_expand_first_match() {
+        let options = tokenizer::TokenizerOptions::default().alternation(true);
+        let parse = |content| parser::parse(&tokenizer::tokenize_with(content, &options).unwrap());
+        let node = parse("{./app.toml|~/.config/app.toml|/etc/app.toml}").unwrap();
+        let exists = |path: &str| path == "~/.config/app.toml" || path == "/etc/app.toml";
+        assert_eq!(
+            expand_first_match(&node, exists),
+            Ok(vec!["~/.config/app.toml".to_owned()])
+        );
+        assert_eq!(expand_first_match(&node, |_| false), Ok(vec![]));
+        // alternations expand like collections otherwise.
+        assert_eq!(
+            expand(&node).unwrap(),
+            ["./app.toml", "~/.config/app.toml", "/etc/app.toml"]
+        );
+        // the leftmost alternation changes slowest, and every comma group keeps a match.
+        let node = parse("{a,b}{x|y}{1|2}").unwrap();
+        let calls = std::cell::RefCell::new(vec![]);
+        let expanded = expand_first_match(&node, |c| {
+            calls.borrow_mut().push(c.to_owned());
+            c.ends_with('2') || c == "by1"
+        });
+        assert_eq!(expanded, Ok(vec!["ax2".to_owned(), "bx2".to_owned()]));
+        assert_eq!(calls.into_inner(), ["ax1", "ax2", "bx1", "bx2"]);
+        // nested groups in an alternative are tried in order too.
+        let node = parse("{{a,b}|c}d").unwrap();
+        assert_eq!(
+            expand_first_match(&node, |c| c != "ad"),
+            Ok(vec!["bd".to_owned()])
+        );
+        let node = parse("{a,b}").unwrap();
+        assert_eq!(
+            expand_first_match(&node, |c| c == "b"),
+            Ok(vec!["b".to_owned()])
+        );
+        assert_eq!(count(&parse("{a|b|}{1..3}").unwrap()), Ok(9));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let canonical = |content: &str| {
+            let options = tokenizer::TokenizerOptions::default().alternation(true);
+            let node =
+                parser::parse(&tokenizer::tokenize_with(content, &options).unwrap()).unwrap();
+            let canonical = canonicalize(&node);
+            assert_eq!(expand(&canonical), expand(&node), "{content}");
+            assert_eq!(canonicalize(&canonical), canonical, "{content}");
+            canonical.to_string()
+        };
+        for (content, expected) in [
+            ("{a}{b}", "ab"),
+            ("x{a}y", "xay"),
+            ("{{a,b}}", "{a,b}"),
+            ("{x{a,b}}y", "x{a,b}y"),
+            ("{{a,b},c}", "{a,b,c}"),
+            ("{a,{b,{c,d}}}", "{a,b,c,d}"),
+            ("{a,a,b}", "{a,a,b}"),
+            ("{a,}{,b}", "{a,}{,b}"),
+            ("{a|{b}}", "{a|b}"),
+            ("{{a|b},c}", "{{a|b},c}"),
+            ("{1..3}{x}{a..c}", "{1..3}x{a..c}"),
+            (
+                "{1..<9..4}{0..10:~3}{1..9:-2}{aa..ac}",
+                "{1..<9..4}{0..10:~3}{1..9:-2}{aa..ac}",
+            ),
+            ("{a\\,b,c\\}}", "{a\\,b,c\\}}"),
+            ("{x\\..y,z}", "{x\\..y,z}"),
+            ("{$}{a,b}", "${a,b}"),
+            ("{1_000..1_002}", "{1000..1002}"),
+        ] {
+            assert_eq!(canonical(content), expected, "{content}");
+        }
+        assert_eq!(canonicalize(&parse("{a}{b}")), canonicalize(&parse("a{b}")));
+        assert_eq!(
+            canonicalize(&parse("p{{a,b},c}s")),
+            canonicalize(&parse("{p}{a,{b},c}{s}"))
+        );
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn test_expand_in() {
+        let bump = bumpalo::Bump::new();
+        assert_expands_like(
+            &["{a,b}{1..3}{x,{y,z}}", "pre{a..c}post", "{a,b}{1..<1}"],
+            |node| expand_in(node, &bump),
+        );
+        let node = parse("{a,b}{1..18446744073709551616}");
+        assert_eq!(expand_in(&node, &bump), Err(expand(&node).unwrap_err()));
+    }
+
+    #[test]
+    fn test_length_bounds() {
+        for content in [
+            "{a,bbbb}{1..10}",
+            "x{-10..5..5}y",
+            "{01..100}",
+            "{-01..1}",
+            "{5..-12..3}",
+            "{0..100:~3}{-3..3:~4}",
+            "{1..10:-2}{a,b}",
+            "{a..e}{é,e}",
+            "{aa..bb}{,x}",
+            "{a,{b,cc{1..3}}}",
+            "{~..\u{800}}",
+            "{\u{E001}..<\u{7F}}",
+            "{\u{23E}..\u{240}:^}",
+        ] {
+            let expanded = expand(&parse(content)).unwrap();
+            let min = expanded.iter().map(String::len).min().unwrap_or(0);
+            let max = expanded.iter().map(String::len).max().unwrap_or(0);
+            assert_eq!(length_bounds(&parse(content)), Ok((min, max)), "{content}");
+        }
+        assert_eq!(length_bounds(&parse("{a,b}{1..<1}")), Ok((0, 0)));
+        // far too many values to expand.
+        assert_eq!(
+            length_bounds(&parse("{-99999999999..999999999999..7}")),
+            Ok((1, 12))
+        );
+        assert_eq!(length_bounds(&parse("{ ..\u{10FFFF}}")), Ok((1, 4)));
+    }
+
+    #[test]
+    fn test_expand_compact() {
+        assert_expands_like(
+            &[
+                "/home/user/{a,b,c}.txt",
+                "pre\\{x{a,b}{1..3}y",
+                "{a,b}c",
+                "x{1..<1}",
+            ],
+            expand_compact,
+        );
+        let expanded = expand_compact(&parse("/srv/{a,b}/{c,d}")).unwrap();
+        assert!(expanded.iter().all(|e| e.prefix() == "/srv/"));
+        assert_eq!(
+            expanded.iter().map(|e| e.suffix()).collect::<Vec<_>>(),
+            ["a/c", "a/d", "b/c", "b/d"]
+        );
+        // the prefix is shared, not copied.
+        assert!(std::ptr::eq(expanded[0].prefix(), expanded[3].prefix()));
+        assert_eq!(expand_compact(&parse("{a,b}c")).unwrap()[0].prefix(), "");
+    }
+
+    #[test]
+    fn test_expand_unique_by() {
+        assert_eq!(
+            expand_unique_by(&parse("{A,a,B}"), str::to_lowercase),
+            Ok(vec!["A".to_owned(), "B".to_owned()])
+        );
+        // the first expansion of each key is kept, in order.
+        assert_eq!(
+            expand_unique_by(&parse("{b,a,bb,c}{1..2}"), |value| value.len()),
+            Ok(vec!["b1".to_owned(), "bb1".to_owned()])
+        );
+        assert_expands_like(&["{a,b}{1..3}", "x{1..<1}"], |node| {
+            expand_unique_by(node, str::to_owned)
+        });
+    }
+
+    #[test]
+    fn test_expand_boxed() {
+        assert_expands_like(
+            &["{a,b}{1..3}", "x{a,{b,c}d}y", "{1..<1}z", "{x}{,y}"],
+            expand_boxed,
+        );
+        assert_eq!(
+            expand_boxed(&parse("{a,b}{0..10:~1}")),
+            Err(ExpansionError::SampleCountTooSmall(1))
+        );
+    }
+
+    #[test]
+    fn test_expand_to_writer_progress() {
+        let mut out = vec![];
+        let mut reports = vec![];
+        let written =
+            expand_to_writer_progress(&parse("{a..c}{0..9999}"), ",", &mut out, |done, total| {
+                reports.push((done, total))
+            });
+        assert_eq!(written, Ok(30_000));
+        assert_eq!(
+            reports,
+            vec![(10_000, 30_000), (20_000, 30_000), (30_000, 30_000)]
+        );
+        let expected = expand(&parse("{a..c}{0..9999}")).unwrap().join(",") + ",";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        // the final report comes even without any expansion.
+        let mut reports = vec![];
+        let written =
+            expand_to_writer_progress(&parse("{1..<1}"), "\n", std::io::sink(), |done, total| {
+                reports.push((done, total))
+            });
+        assert_eq!(written, Ok(0));
+        assert_eq!(reports, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_expand_lossy() {
+        assert_expands_like(
+            &["{a,b}{1..3}", "x{a,{b,c}d}y", "{1..<1}z", "{0..10:~3}"],
+            |node| expand_lossy(node).into_iter().collect(),
+        );
+        let too_large = ExpansionError::NumConversionFailed("18446744073709551616".to_owned());
+        assert_eq!(
+            expand_lossy(&parse("x{{1..2},{1..18446744073709551616},b}{y,z}")),
+            vec![
+                Ok("x1y".to_owned()),
+                Ok("x1z".to_owned()),
+                Ok("x2y".to_owned()),
+                Ok("x2z".to_owned()),
+                Err(too_large.clone()),
+                Err(too_large.clone()),
+                Ok("xby".to_owned()),
+                Ok("xbz".to_owned()),
+            ]
+        );
+        assert_eq!(
+            expand_lossy(&parse("{0..10:~1}")),
+            vec![Err(ExpansionError::SampleCountTooSmall(1))]
+        );
+    }
+
+    #[test]
+    fn test_render_error() {
+        let render = |content| render_error(content, &bracoxidize(content).unwrap_err());
+        assert_eq!(
+            render("{a,b}}"),
+            "{a,b}}\n     ^\nParsing failed: Used extra closing bracket at 5"
+        );
+        assert_eq!(
+            render("x\ny{a,b}}z"),
+            "y{a,b}}z\n      ^\nParsing failed: Used extra closing bracket at 8"
+        );
+        assert_eq!(
+            render("abc"),
+            "Tokenization failed: No braces have been used."
+        );
+        let content = "{a,b}{1..5}";
+        let options = ExpandOptions::default().max_range_len(2);
+        let error = bracoxidize_with(content, &options).unwrap_err();
+        assert_eq!(
+            render_error(content, &error),
+            format!("{content}\n     ^^^^^^\n{error}")
+        );
+    }
+
+    #[test]
+    fn test_expand_selection() {
+        for content in [
+            "{a,b}{1..3}",
+            "x{a,{b,c}d}y{-2..2..2}z",
+            "{x}/{1..3}",
+            "{a..c:^}{a,b}",
+            "pre{a,b}",
+        ] {
+            let node = parse(content);
+            let parts: Vec<_> = chain_groups(&node)
+                .filter(|(_, is_group)| *is_group)
+                .map(|(part, _)| expand(part).unwrap())
+                .collect();
+            // round trip the choices of every combination, as the odometer picks them.
+            let mut choices = vec![0; parts.len()];
+            for expanded in expand(&node).unwrap() {
+                assert_eq!(expand_selection(&node, &choices), Ok(expanded), "{content}");
+                advance(&parts, &mut choices);
+            }
+        }
+        let pattern = parse("{a,b}/{1..3}");
+        assert_eq!(
+            expand_selection(&pattern, &[1, 3]),
+            Err(ExpansionError::ChoiceOutOfRange {
+                group: 1,
+                index: 3,
+                len: 3
+            })
+        );
+        assert_eq!(
+            expand_selection(&pattern, &[1]),
+            Err(ExpansionError::ChoiceMissing(1))
+        );
+        assert_eq!(
+            expand_selection(&pattern, &[1, 0, 0]),
+            Err(ExpansionError::GroupNotFound(2))
+        );
+        assert_eq!(
+            expand_selection(&parse("{x}{a,b}"), &[1, 0]),
+            Err(ExpansionError::ChoiceOutOfRange {
+                group: 0,
+                index: 1,
+                len: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_estimated_bytes() {
+        for content in [
+            "{a,bb}{1..10}",
+            "x{-10..5..5}y",
+            "{01..100}",
+            "{-01..1}{,z}",
+            "{5..-12..3}",
+            "{0..100:~3}{-3..3:~4}",
+            "{1..10:-2}{a,b}",
+            "{a..e:^}{é,e}",
+            "{aa..bb}{,x}",
+            "pre{a,{b,cc{1..3}}}post",
+            "{a,b}{1..<1}",
+            "{~..\u{800}}",
+            "{\u{D7FE}..\u{E001}}x",
+            "{\u{E001}..<\u{7F}}",
+            "{\u{23E}..\u{240}:^}",
+        ] {
+            let bytes = expand(&parse(content))
+                .unwrap()
+                .iter()
+                .map(String::len)
+                .sum();
+            assert_eq!(estimated_bytes(&parse(content)), Ok(bytes), "{content}");
+        }
+        // far too many values to expand: 9 of 1 digit, 90 of 2, and so on.
+        let bytes = (1..=12).map(|digits| 9 * 10_usize.pow(digits - 1) * digits as usize);
+        assert_eq!(
+            estimated_bytes(&parse("{1..999999999999}")),
+            Ok(bytes.sum())
+        );
+        // 96 chars of 1 byte, 1_920 of 2, 61_440 of 3 (less the surrogates) and 1_048_576 of 4.
+        assert_eq!(estimated_bytes(&parse("{ ..\u{10FFFF}}")), Ok(4_382_560));
+        assert!(matches!(
+            estimated_bytes(&parse("{1..18446744073709551615}")),
+            Err(ExpansionError::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expand_group() {
+        let pattern = parse("{env}/{a,{b,c}}/{1..2}x");
+        assert_eq!(
+            expand_group(&pattern, 0).unwrap(),
+            ["env/{a,{b,c}}/{1..2}x"]
+        );
+        assert_eq!(
+            expand_group(&pattern, 1).unwrap(),
+            ["{env}/a/{1..2}x", "{env}/b/{1..2}x", "{env}/c/{1..2}x"]
+        );
+        assert_eq!(
+            expand_group(&pattern, 2).unwrap(),
+            ["{env}/{a,{b,c}}/1x", "{env}/{a,{b,c}}/2x"]
+        );
+        assert_eq!(
+            expand_group(&pattern, 3),
+            Err(ExpansionError::GroupNotFound(3))
+        );
+        // every stage expands to the same values in the end.
+        for group in 0..3 {
+            let mut staged = vec![];
+            for partial in expand_group(&pattern, group).unwrap() {
+                staged.extend(bracoxidize(&partial).unwrap());
+            }
+            staged.sort();
+            let mut expanded = expand(&pattern).unwrap();
+            expanded.sort();
+            assert_eq!(staged, expanded);
+        }
+        // the values are escaped, and so are the texts around them.
+        assert_eq!(
+            expand_group(&parse("{a\\,b,.}.c{d,e}"), 0).unwrap(),
+            ["a\\,b.c{d,e}", "\\..c{d,e}"]
+        );
+        assert_eq!(expand_group(&parse("{a,b}"), 0).unwrap(), ["a", "b"]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_expand_sample() {
+        let pattern = parse("{a,b}{1..30}{x,{y,z}}");
+        let expanded = expand(&pattern).unwrap();
+        for seed in 0..20 {
+            let sample = expand_sample(&pattern, 10, seed).unwrap();
+            assert_eq!(sample, expand_sample(&pattern, 10, seed).unwrap());
+            assert_eq!(sample.len(), 10);
+            // distinct expansions, in the order of expand.
+            let positions: Vec<usize> = sample
+                .iter()
+                .map(|s| expanded.iter().position(|e| e == s).unwrap())
+                .collect();
+            assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        }
+        assert_eq!(expand_sample(&pattern, 1000, 7).unwrap(), expanded);
+        assert_eq!(expand_sample(&parse("{a,b}{1..<1}"), 5, 7), Ok(vec![]));
+        // (10^6)^3 combinations, far too many to expand.
+        let huge = parse("{0..999999}{0..999999}{0..999999}");
+        let sample = expand_sample(&huge, 5, 1).unwrap();
+        assert_eq!(sample.len(), 5);
+        assert!(sample.iter().all(|s| s.len() <= 18));
+        assert_ne!(sample, expand_sample(&huge, 5, 2).unwrap());
+    }
+
+    #[test]
+    fn test_expand_from() {
+        for content in [
+            "{a,b}{1..3}{x,{y,z}}",
+            "pre{a..c}mid{1..2}post",
+            "{1..5}",
+            "x{a,b}",
+        ] {
+            let node = parse(content);
+            let expanded = expand(&node).unwrap();
+            for cursor in 0..=expanded.len() + 1 {
+                for len in 0..4 {
+                    let end = (cursor + len).min(expanded.len());
+                    let slice = expanded.get(cursor..end).unwrap_or_default();
+                    assert_eq!(expand_from(&node, cursor, len).unwrap(), slice, "{content}");
+                }
+            }
+        }
+        // (10^6)^3 combinations, far too many to expand up to the cursor.
+        let node = parse("{0..999999}{0..999999}{0..999999}");
+        assert_eq!(expand_from(&node, usize::MAX, 2), Ok(vec![]));
+        let cursor = 123_456 * 1_000_000_000_000 + 7 * 1_000_000 + 999_999;
+        assert_eq!(
+            expand_from(&node, cursor, 2),
+            Ok(vec!["1234567999999".to_owned(), "12345680".to_owned()])
+        );
+        let node = parse("{a,b}{1..<1}");
+        assert_eq!(expand_from(&node, 0, 5), Ok(vec![]));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_expand_iter() {
+        use rayon::prelude::*;
+        for content in [
+            "{a,b}{1..30}{x,{y,z}}",
+            "pre{a..c}mid{1..2}post",
+            "x{a,b}",
+            "{a,b}{1..<1}",
+        ] {
+            let node = parse(content);
+            let mut indexed: Vec<(usize, String)> = par_expand_iter(&node)
+                .enumerate()
+                .map(|(i, expanded)| (i, expanded.unwrap()))
+                .collect();
+            indexed.sort();
+            let expanded: Vec<String> = indexed.into_iter().map(|(_, s)| s).collect();
+            assert_eq!(expanded, expand(&node).unwrap(), "{content}");
+        }
+        let node = parse("{a,b}{1..18446744073709551616}");
+        let expanded: Vec<_> = par_expand_iter(&node).collect();
+        assert_eq!(expanded, vec![Err(expand(&node).unwrap_err())]);
+        let node = parse(&"{0..99999}".repeat(4));
+        let expanded: Vec<_> = par_expand_iter(&node).collect();
+        assert!(matches!(
+            expanded[..],
+            [Err(ExpansionError::LimitExceeded {
+                limit: usize::MAX,
+                ..
+            })]
+        ));
+    }
+
+    #[test]
+    fn test_max_output_bytes() {
+        let options = ExpandOptions::default().max_output_bytes(8);
+        assert_eq!(
+            bracoxidize_with("{a,b}{1..3}", &options),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::OutputTooLarge { bytes: 10, max: 8 }
+            ))
+        );
+        assert_eq!(
+            bracoxidize_with("{a,b}{1..2}", &options),
+            Ok(vec![
+                "a1".to_owned(),
+                "a2".to_owned(),
+                "b1".to_owned(),
+                "b2".to_owned()
+            ])
+        );
+        let huge = format!("{}{{0..0}}", "x".repeat(100));
+        assert_eq!(
+            bracoxidize_with(&huge, &options),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::OutputTooLarge { bytes: 101, max: 8 }
+            ))
+        );
+        // the expansions of every part fit, only their combinations don't.
+        let options = ExpandOptions::default().max_output_bytes(20);
+        let node = parse("{a,b}{c,d}{e,f}");
+        let error = ExpansionError::OutputTooLarge { bytes: 21, max: 20 };
+        assert_eq!(expand_with(&node, &options), Err(error.clone()));
+        assert_eq!(Expander::new(options.clone()).expand(&node), Err(error));
+        assert_eq!(
+            expand_with(&node, &options.max_output_bytes(24))
+                .unwrap()
+                .len(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        let grouped = NumberFormat::default().grouping(',');
+        let options = ExpandOptions::default().number_format(grouped);
+        let expand = |content| bracoxidize_with(content, &options).unwrap();
+        assert_eq!(expand("{1000..1002}"), ["1,000", "1,001", "1,002"]);
+        assert_eq!(expand("{-1000..1000..1000}"), ["-1,000", "0", "1,000"]);
+        assert_eq!(expand("{0..2000000:~2}"), ["0", "2,000,000"]);
+        // padded to the digit count first, then grouped.
+        assert_eq!(expand("{0999..1000}"), ["0,999", "1,000"]);
+        assert_eq!(expand("{-0999..-1000}"), ["-0,999", "-1,000"]);
+        // text and collections are left untouched.
+        assert_eq!(expand("1000{1000,2000..2000}"), ["10001000", "10002,000"]);
+        let options = options.range_format(|i| format!("#{i}"));
+        assert_eq!(
+            bracoxidize_with("{1000..1001}", &options),
+            Ok(vec!["#1000".to_owned(), "#1001".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{1000..1001}"),
+            Ok(vec!["1000".to_owned(), "1001".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_range_format() {
+        let options = ExpandOptions::default().range_format(|i| {
+            let digits = i.to_string();
+            let mut formatted = String::new();
+            for (n, c) in digits.chars().enumerate() {
+                if n > 0 && (digits.len() - n) % 3 == 0 {
+                    formatted.push(',');
+                }
+                formatted.push(c);
+            }
+            formatted
+        });
+        assert_eq!(
+            bracoxidize_with("{999..1001}", &options),
+            Ok(vec![
+                "999".to_owned(),
+                "1,000".to_owned(),
+                "1,001".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize_with("{0..2000000:~2}", &options),
+            Ok(vec!["0".to_owned(), "2,000,000".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with("1000{1000,a}", &options),
+            Ok(vec!["10001000".to_owned(), "1000a".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_bracoxidize_grouped() {
+        let group = |value: &str, expansions: &[&str]| {
+            (
+                value.to_owned(),
+                expansions.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            bracoxidize_grouped("{dir1,dir2}/{a,b}"),
+            Ok(vec![
+                group("dir1", &["dir1/a", "dir1/b"]),
+                group("dir2", &["dir2/a", "dir2/b"])
+            ])
+        );
+        assert_eq!(
+            bracoxidize_grouped("x/{a{1,2},b}/y"),
+            Ok(vec![
+                group("a1", &["x/a1/y"]),
+                group("a2", &["x/a2/y"]),
+                group("b", &["x/b/y"])
+            ])
+        );
+        assert_eq!(
+            bracoxidize_grouped("{1..2}{a,b}"),
+            Ok(vec![group("1", &["1a", "1b"]), group("2", &["2a", "2b"])])
+        );
+        assert_eq!(bracoxidize_grouped("x{a}y"), Ok(vec![group("a", &["xay"])]));
+        assert_eq!(
+            bracoxidize_grouped("{a,b"),
+            Err(OxidizationError::TokenizationError(
+                tokenizer::TokenizationError::FormatNotSupported
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bracoxidize_to_path() {
+        let path =
+            std::env::temp_dir().join(format!("bracoxide-test-to-path-{}.txt", std::process::id()));
+        let content = "x{a,b{1..3}}-{c..<e}";
+        assert_eq!(bracoxidize_to_path(content, &path), Ok(8));
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            written.lines().collect::<Vec<_>>(),
+            bracoxidize(content).unwrap()
+        );
+        assert_eq!(bracoxidize_to_path("a{1..<1}", &path), Ok(0));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            bracoxidize_to_path("{a,b}", &path.join("nested")),
+            Err(OxidizationError::IoError(std::io::ErrorKind::NotFound))
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let spans = |content| {
+            validate(content).map(|lints| {
+                lints
+                    .into_iter()
+                    .map(|l| (l.span.start, l.span.end))
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(spans("{a}"), Ok(vec![(0, 3)]));
+        assert_eq!(spans("{1..1}"), Ok(vec![(0, 6)]));
+        assert_eq!(spans("x{a,{b}}{1..1}y"), Ok(vec![(4, 7), (8, 14)]));
+        assert_eq!(spans("{a,b}{1..2}{a..<c}"), Ok(vec![]));
+        assert_eq!(spans("{x{a,b}}"), Ok(vec![]));
+        assert_eq!(
+            validate("{1..1}").unwrap()[0].message,
+            "range with a single value has nothing to expand"
+        );
+        assert_eq!(
+            validate("{a,b"),
+            Err(OxidizationError::TokenizationError(
+                tokenizer::TokenizationError::FormatNotSupported
+            ))
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            explain("{a,{b,c}}{1..3}").unwrap(),
+            "sequence at 0..15, 9 combinations
+  group at 0..9, 3 combinations
+    text \"a\" at 1..2
+    group at 3..8, 2 combinations
+      text \"b\" at 4..5
+      text \"c\" at 6..7
+  range from 1 to 3 at 9..15, 3 combinations
+"
+        );
+        assert_eq!(
+            explain("{a..<e}{0..10:~3}").unwrap(),
+            "sequence at 0..17, 12 combinations
+  char range from 'a' to 'e' (exclusive) at 0..7, 4 combinations
+  range from 0 to 10 sampling 3 at 7..17, 3 combinations
+"
+        );
+        assert_eq!(
+            explain("{a,b"),
+            Err(OxidizationError::TokenizationError(
+                tokenizer::TokenizationError::FormatNotSupported
+            ))
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        for content in [
+            "{a,b}{1..3}x{y,z}",
+            "{a..e}{,x}",
+            "{5..1}{1..<3}",
+            "a{b{c,d},e}f",
+        ] {
+            let ast = parse(content);
+            assert_eq!(count(&ast), Ok(expand(&ast).unwrap().len()));
+        }
+    }
+
+    #[test]
+    fn test_range_sample() {
+        assert_eq!(
+            bracoxidize("{0..100:~5}"),
+            Ok(vec![
+                "0".to_owned(),
+                "25".to_owned(),
+                "50".to_owned(),
+                "75".to_owned(),
+                "100".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{0..10:~3}"),
+            Ok(vec!["0".to_owned(), "5".to_owned(), "10".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("x{0..10:~4}"),
+            Ok(vec![
+                "x0".to_owned(),
+                "x3".to_owned(),
+                "x7".to_owned(),
+                "x10".to_owned()
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{0..10:~1}"),
+            Err(OxidizationError::ExpansionError(
+                ExpansionError::SampleCountTooSmall(1)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{0..10:~}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::SampleCountExpected(6)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{a..z:~3}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_shell_literals() {
+        let expand = |content| bracoxidize(content).unwrap();
+        assert_eq!(
+            expand("~/{.config,.local}/app"),
+            ["~/.config/app", "~/.local/app"]
+        );
+        assert_eq!(expand("$HOME/{a,b}"), ["$HOME/a", "$HOME/b"]);
+        assert_eq!(expand("x/{.config}/y"), ["x/.config/y"]);
+        assert_eq!(expand("{.a,.b}"), [".a", ".b"]);
+    }
+
+    #[test]
+    fn test_top_level_separators() {
+        let expanded = Ok(vec!["a,bc".to_owned(), "a,bd".to_owned()]);
+        assert_eq!(bracoxidize("a\\,b{c,d}"), expanded);
+        assert_eq!(bracoxidize("a,b{c,d}"), expanded);
+        assert_eq!(
+            bracoxidize("{c,d}a,b"),
+            Ok(vec!["ca,b".to_owned(), "da,b".to_owned()])
+        );
+        // range operators outside of braces are still an error.
+        assert_eq!(
+            bracoxidize("a..b{c,d}"),
+            Err(OxidizationError::ParsingError(ParsingError::OBraExpected(
+                1
+            )))
+        );
+    }
+
+    #[test]
+    fn test_leading_brace() {
+        assert_eq!(
+            bracoxidize("{a,b}"),
+            Ok(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{a,b}{c,d}"),
+            Ok(vec![
+                "ac".to_owned(),
+                "ad".to_owned(),
+                "bc".to_owned(),
+                "bd".to_owned()
+            ])
+        );
+        let node = parse("{a,b}");
+        let choices: Vec<_> = expand_spanned(&node)
+            .unwrap()
+            .into_iter()
+            .map(|expansion| expansion.choices)
+            .collect();
+        let choice = |item| vec![Choice { group: 0..5, item }];
+        assert_eq!(choices, vec![choice(1..2), choice(3..4)]);
+    }
+
+    #[test]
+    fn test_lone_dots() {
+        // a single dot is literal, not a partial range.
+        for content in ["{a.b}", "{.a}", "{a.}", "{.}"] {
+            let expected = content.trim_matches(['{', '}']).to_owned();
+            assert_eq!(bracoxidize(content), Ok(vec![expected]), "{content}");
+        }
+        assert_eq!(
+            bracoxidize("x{a.b,.c}y"),
+            Ok(vec!["xa.by".to_owned(), "x.cy".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_padding_with_prefix() {
+        let expand = |content| bracoxidize(content).unwrap();
+        let ids: Vec<String> = (1..=10).map(|i| format!("id{i:03}")).collect();
+        assert_eq!(expand("id{001..010}"), ids);
+        // the digits of the prefix and the postfix don't count toward the width.
+        assert_eq!(expand("id00{1..3}"), ["id001", "id002", "id003"]);
+        assert_eq!(expand("v2{08..10}.1"), ["v208.1", "v209.1", "v210.1"]);
+        let node = parse("id{001..010}");
+        assert_eq!(length_bounds(&node), Ok((5, 5)));
+    }
+
+    #[test]
+    fn test_hex_range() {
+        let expand = |content| bracoxidize(content).unwrap();
+        let bytes: Vec<String> = (0..=255).map(|i| format!("{i:02x}")).collect();
+        assert_eq!(expand("{0x00..0xff}"), bytes);
+        // padded to the digits of the wider limit.
+        assert_eq!(expand("{0x0..0x10}")[..3], ["00", "01", "02"]);
+        assert_eq!(expand("{0x0..0x10}")[16], "10");
+        assert_eq!(expand("{0xfe..0x100}"), ["0fe", "0ff", "100"]);
+        assert_eq!(expand("#{0x0A..0x0C}"), ["#0A", "#0B", "#0C"]);
+        assert_eq!(expand("{-0x2..0x2}"), ["-2", "-1", "0", "1", "2"]);
+        assert_eq!(expand("{0x0..0xff..64}"), ["00", "40", "80", "c0"]);
+        assert_eq!(expand("{0x00..0xff:~3}"), ["00", "80", "ff"]);
+        assert_eq!(expand("{0xff..<0xfc}"), ["ff", "fe", "fd"]);
+        // the digits of hex values aren't grouped.
+        let options = ExpandOptions::default().number_format(NumberFormat::default().grouping(','));
+        assert_eq!(
+            bracoxidize_with("{0xfff..0x1000}", &options),
+            Ok(vec!["0fff".to_owned(), "1000".to_owned()])
+        );
+        let node = parse("{0x00..0xff}");
+        assert_eq!(node.to_string(), "{0x00..0xff}");
+        assert_eq!(length_bounds(&node), Ok((2, 2)));
+        assert_eq!(estimated_bytes(&node), Ok(512));
+        assert_eq!(expand_numeric(&node).unwrap()[255], 255);
+    }
+
+    #[test]
+    fn test_collection_then_range() {
+        let expand = |content| bracoxidize(content).unwrap();
+        // the leftmost group changes slowest, and the trailing text follows every value.
+        assert_eq!(expand("{x,y}{1..2}z"), ["x1z", "x2z", "y1z", "y2z"]);
+        assert_eq!(expand("a{x,y}{2..1}z"), ["ax2z", "ax1z", "ay2z", "ay1z"]);
+        let node = parse("{x,y}{1..2}z");
+        let parts = chain_parts(&node);
+        assert!(matches!(
+            parts[0],
+            Node::Collection {
                 start: 0,
-                end: 26
-            }),
+                end: 4,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parts[1],
+            Node::Range {
+                start: 5,
+                end: 10,
+                ..
+            }
+        ));
+        assert!(matches!(parts[2], Node::Text { message, start: 11, .. } if **message == "z"));
+        assert_eq!(count(&node), Ok(4));
+    }
+
+    #[test]
+    fn test_char_range_case() {
+        let expand = |content| bracoxidize(content).unwrap();
+        assert_eq!(expand("{a..c:^}"), ["A", "B", "C"]);
+        assert_eq!(expand("{C..<A:_}"), ["c", "b"]);
+        assert_eq!(expand("x{a..b:^}{1,2}"), ["xA1", "xA2", "xB1", "xB2"]);
+        assert_eq!(expand("{ß..ß:^}"), ["SS"]);
+        let options = ExpandOptions::default().char_range_mode(CharRangeMode::AlnumOnly);
+        assert_eq!(
+            bracoxidize_with("{Y..b:_}", &options).unwrap(),
+            ["y", "z", "a", "b"]
+        );
+        assert_eq!(
+            bracoxidize("{1..3:^}"),
+            Err(OxidizationError::ParsingError(
+                ParsingError::RangeCantHaveText(5)
+            ))
+        );
+        assert!(bracoxidize("{aa..ac:^}").is_err());
+        assert!(explain("{a..c:^}").unwrap().contains("in uppercase"));
+    }
+
+    #[test]
+    fn test_bash_conformance() {
+        // Expected outputs are taken from `bash -c "echo <pattern>"`.
+        let cases = [
+            ("{a,b}{c,d}", "ac ad bc bd"),
+            ("a{b,{c,d}e}f", "abf acef adef"),
+            ("{a,b}c", "ac bc"),
+            ("x{a,b,c}y", "xay xby xcy"),
+            ("{1..3}", "1 2 3"),
+            ("{3..1}", "3 2 1"),
+            ("{1..3}{a,b}", "1a 1b 2a 2b 3a 3b"),
+            ("a{b,c}d{e,f}g", "abdeg abdfg acdeg acdfg"),
+            ("{{a,b},c}", "a b c"),
+            ("{a,{b,{c,d}}}", "a b c d"),
+            ("a{b{c,d},e}f", "abcf abdf aef"),
+            ("{a,b{1..2}}", "a b1 b2"),
+            ("{a..c}", "a b c"),
+            ("{c..a}", "c b a"),
+            ("{1..3}x{y,z}", "1xy 1xz 2xy 2xz 3xy 3xz"),
+            ("{a,b}{1..2}{x,y}", "a1x a1y a2x a2y b1x b1y b2x b2y"),
+            ("{x,y{1,2}z}w", "xw y1zw y2zw"),
+            ("pre{,a,b}post", "prepost preapost prebpost"),
+            ("{1..10}", "1 2 3 4 5 6 7 8 9 10"),
+            ("{01..10}", "01 02 03 04 05 06 07 08 09 10"),
+            ("{10..1}", "10 9 8 7 6 5 4 3 2 1"),
+            ("{-01..01}", "-01 000 001"),
+            ("{-2..2}", "-2 -1 0 1 2"),
+            ("{1..010}", "001 002 003 004 005 006 007 008 009 010"),
+            ("{5..-01}", "005 004 003 002 001 000 -01"),
+            ("{00..-1}", "00 -1"),
+            ("{-0..2}", "0 1 2"),
+            ("{1..10..2}", "1 3 5 7 9"),
+            ("{10..1..2}", "10 8 6 4 2"),
+            ("{1..10..-2}", "1 3 5 7 9"),
+            ("{1..5..0}", "1 2 3 4 5"),
+            ("{01..10..3}", "01 04 07 10"),
+            ("{1..2..5}", "1"),
+            ("{a..c}{1..3}", "a1 a2 a3 b1 b2 b3 c1 c2 c3"),
+            ("{1..3}{4..6}", "14 15 16 24 25 26 34 35 36"),
+            ("{1..2}{4..5}{7..8}", "147 148 157 158 247 248 257 258"),
+            ("a,b{c,d}", "a,bc a,bd"),
+            ("{a,b},{c,d}", "a,c a,d b,c b,d"),
+        ];
+        for (pattern, expected) in cases {
+            let expected: Vec<String> = expected.split(' ').map(str::to_owned).collect();
+            assert_eq!(bracoxidize(pattern), Ok(expected), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_digit_groups() {
+        let expand = |content| bracoxidize(content).unwrap();
+        assert_eq!(expand("{1_000..1_002}"), ["1000", "1001", "1002"]);
+        assert_eq!(expand("{0_001..0_003}"), ["0001", "0002", "0003"]);
+        assert_eq!(expand("{0..1_000..500}"), ["0", "500", "1000"]);
+        assert_eq!(expand("{-1_0..-9}"), ["-10", "-9"]);
+        // outside of a range the number is text, which is kept as written.
+        assert_eq!(expand("{1_000,b}"), ["1_000", "b"]);
+    }
+
+    #[test]
+    fn test_range_step() {
+        assert_eq!(
+            bracoxidize("{1..<9..4}"),
+            Ok(vec!["1".to_owned(), "5".to_owned()])
+        );
+        let node = parse("{0..100..10}");
+        assert_eq!(count(&node), Ok(11));
+        assert_eq!(
+            bracoxidize("{1..10..}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::StepExpected(6)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{1..10..<2}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::ExtraRangeOperator(6)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{1..10..2..3}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::ExtraRangeOperator(9)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_signed_range_step() {
+        let expanded = |content| bracoxidize(content).unwrap().join(" ");
+        // the sign of the step goes along with the limits.
+        assert_eq!(expanded("{10..1:-2}"), "10 8 6 4 2");
+        assert_eq!(expanded("{1..10:+2}"), "1 3 5 7 9");
+        // the sign goes against the limits, so the range is empty.
+        assert_eq!(expanded("{1..10:-2}"), "");
+        assert_eq!(expanded("{10..1:+2}"), "");
+        // without a sign, the limits decide the direction.
+        assert_eq!(expanded("{10..1}"), "10 9 8 7 6 5 4 3 2 1");
+        assert_eq!(expanded("{10..1..-3}"), "10 7 4 1");
+        assert_eq!(expanded("{5..5:-1}"), "5");
+        assert_eq!(expanded("{10..<1:-3}"), "10 7 4");
+        let node = parse("{1..10:-2}");
+        assert_eq!(count(&node), Ok(0));
+        assert_eq!(
+            bracoxidize("{10..1:-}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::StepExpected(6)
+            ))
+        );
+        assert_eq!(
+            bracoxidize("{1..10:~2:+2}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::RangeCantHaveText(9)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_redundant_braces() {
+        let expanded = |content| bracoxidize(content).unwrap().join(" ");
+        assert_eq!(expanded("{{a,b}}"), "a b");
+        assert_eq!(expanded("{x{a,b}}"), "xa xb");
+        assert_eq!(expanded("{{a,b}y}c"), "ayc byc");
+        assert_eq!(expanded("{{{a,b}}}"), "a b");
+        assert_eq!(expanded("x{{1..3}}"), "x1 x2 x3");
+        assert_eq!(expanded("{a,{{b,c}}}"), "a b c");
+        let node = parse("{x{a,b}}");
+        assert_eq!(count(&node), Ok(2));
+        assert_eq!(max_depth(&node), 2);
+    }
+
+    #[test]
+    fn test_range_between_numbers() {
+        let expanded = |content| bracoxidize(content).unwrap().join(" ");
+        assert_eq!(expanded("9{1..2}9"), "919 929");
+        assert_eq!(expanded("{1..2}99"), "199 299");
+        assert_eq!(expanded("99{1..2}"), "991 992");
+        assert_eq!(expanded("9{01..3}9"), "9019 9029 9039");
+        let node = parse("9{1..2}9");
+        let parts: Vec<_> = chain_parts(&node).iter().map(|n| n.span()).collect();
+        assert_eq!(parts, [0..1, 1..7, 7..8]);
+    }
+
+    #[test]
+    fn test_descending_range() {
+        assert_eq!(
+            bracoxidize("{3..<1}"),
+            Ok(vec!["3".to_owned(), "2".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{10..0:~3}"),
+            Ok(vec!["10".to_owned(), "5".to_owned(), "0".to_owned()])
+        );
+        assert_eq!(bracoxidize("{3..<3}"), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_quotes() {
+        let options =
+            ExpandOptions::default().tokenizer(tokenizer::TokenizerOptions::default().quotes(true));
+        assert_eq!(
+            bracoxidize_with("'{a,b}'{c,d}", &options),
+            Ok(vec!["{a,b}c".to_owned(), "{a,b}d".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with("\"{a,b}\"{c,d}", &options),
             Ok(vec![
-                "ABHJL3".to_owned(),
-                "ABHJL4".to_owned(),
-                "ABHJL5".to_owned(),
-                "ABHKL3".to_owned(),
-                "ABHKL4".to_owned(),
-                "ABHKL5".to_owned(),
-                "ACDFHJL3".to_owned(),
-                "ACDFHJL4".to_owned(),
-                "ACDFHJL5".to_owned(),
-                "ACDFHKL3".to_owned(),
-                "ACDFHKL4".to_owned(),
-                "ACDFHKL5".to_owned(),
-                "ACEFHJL3".to_owned(),
-                "ACEFHJL4".to_owned(),
-                "ACEFHJL5".to_owned(),
-                "ACEFHKL3".to_owned(),
-                "ACEFHKL4".to_owned(),
-                "ACEFHKL5".to_owned(),
-                "AGHJL3".to_owned(),
-                "AGHJL4".to_owned(),
-                "AGHJL5".to_owned(),
-                "AGHKL3".to_owned(),
-                "AGHKL4".to_owned(),
-                "AGHKL5".to_owned(),
+                "ac".to_owned(),
+                "ad".to_owned(),
+                "bc".to_owned(),
+                "bd".to_owned()
             ])
-        )
+        );
+        assert_eq!(
+            bracoxidize_with("\"x y\\z\"{1..2}", &options),
+            Ok(vec!["x y\\z1".to_owned(), "x y\\z2".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with("\"a\\b\\,\"{c,'d,\"'}", &options),
+            Ok(vec!["a\\b,c".to_owned(), "a\\b,d,\"".to_owned()])
+        );
     }
+
     #[test]
-    fn test_expand_complex_bracoxidize() {
+    fn test_comments() {
+        let options = ExpandOptions::default().allow_comments(true);
         assert_eq!(
-            bracoxidize("A{B,C{D,E}F,G}H{J,K}L{3..5}"),
+            bracoxidize_with("{a,b} # choose one\n{c,d}", &options),
             Ok(vec![
-                "ABHJL3".to_owned(),
-                "ABHJL4".to_owned(),
-                "ABHJL5".to_owned(),
-                "ABHKL3".to_owned(),
-                "ABHKL4".to_owned(),
-                "ABHKL5".to_owned(),
-                "ACDFHJL3".to_owned(),
-                "ACDFHJL4".to_owned(),
-                "ACDFHJL5".to_owned(),
-                "ACDFHKL3".to_owned(),
-                "ACDFHKL4".to_owned(),
-                "ACDFHKL5".to_owned(),
-                "ACEFHJL3".to_owned(),
-                "ACEFHJL4".to_owned(),
-                "ACEFHJL5".to_owned(),
-                "ACEFHKL3".to_owned(),
-                "ACEFHKL4".to_owned(),
-                "ACEFHKL5".to_owned(),
-                "AGHJL3".to_owned(),
-                "AGHJL4".to_owned(),
-                "AGHJL5".to_owned(),
-                "AGHKL3".to_owned(),
-                "AGHKL4".to_owned(),
-                "AGHKL5".to_owned(),
+                "a \nc".to_owned(),
+                "a \nd".to_owned(),
+                "b \nc".to_owned(),
+                "b \nd".to_owned()
             ])
-        )
+        );
+        assert_eq!(
+            bracoxidize_with("{a,b}\\# # {c,d}", &options),
+            Ok(vec!["a# ".to_owned(), "b# ".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{a,b}#{c}"),
+            Ok(vec!["a#c".to_owned(), "b#c".to_owned()])
+        );
+        let options = options.tokenizer(tokenizer::TokenizerOptions::default().quotes(true));
+        assert_eq!(
+            bracoxidize_with("{'#a',\"#b\"} #c", &options),
+            Ok(vec!["#a ".to_owned(), "#b ".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_comments_error_positions() {
+        let options = ExpandOptions::default().allow_comments(true);
+        let content = "# c\n{a,b}}";
+        let err = bracoxidize_with(content, &options).unwrap_err();
+        assert_eq!(
+            err,
+            OxidizationError::ParsingError(ParsingError::ExtraCBra(9))
+        );
+        assert_eq!(render_error(content, &err).lines().nth(1), Some("     ^"));
+        let err = bracoxidize_with("{a,b} # one\n{x,y} # two\n}", &options).unwrap_err();
+        assert_eq!(
+            err,
+            OxidizationError::ParsingError(ParsingError::ExtraCBra(24))
+        );
+        let tokenizer = tokenizer::TokenizerOptions::default().quotes(true);
+        let options = options.tokenizer(tokenizer);
+        assert_eq!(
+            bracoxidize_with("# c\n{a,b}'", &options),
+            Err(OxidizationError::TokenizationError(
+                tokenizer::TokenizationError::UnclosedQuote(9)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verbatim_groups() {
+        let tokenizer = tokenizer::TokenizerOptions::default().verbatim_groups(true);
+        let options = ExpandOptions::default().tokenizer(tokenizer);
+        let expand = |content| bracoxidize_with(content, &options).unwrap();
+        assert_eq!(expand("%{a,b}"), ["{a,b}"]);
+        assert_eq!(expand("{a,b}"), ["a", "b"]);
+        assert_eq!(expand("%{a,{b,c}}-{x,y}"), ["{a,{b,c}}-x", "{a,{b,c}}-y"]);
+        assert_eq!(expand("{%{a,b},c}"), ["{a,b}", "c"]);
+        assert_eq!(
+            bracoxidize("%{a,b}"),
+            Ok(vec!["%a".to_owned(), "%b".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_windows_paths() {
+        let tokenizer = tokenizer::TokenizerOptions::default().literal_backslash(true);
+        let options = ExpandOptions::default().tokenizer(tokenizer);
+        assert_eq!(
+            bracoxidize_with("C:\\a\\{b,c}", &options),
+            Ok(vec!["C:\\a\\b".to_owned(), "C:\\a\\c".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize_with("C:\\Users\\{alice,bob}\\docs", &options),
+            Ok(vec![
+                "C:\\Users\\alice\\docs".to_owned(),
+                "C:\\Users\\bob\\docs".to_owned()
+            ])
+        );
+        // a backslash before a comment doesn't escape it either.
+        let options = options.allow_comments(true);
+        assert_eq!(
+            bracoxidize_with("{a,b}\\# c", &options),
+            Ok(vec!["a\\".to_owned(), "b\\".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_adjacent_groups() {
+        assert_eq!(bracoxidize(&"{a}".repeat(500)), Ok(vec!["a".repeat(500)]));
+        assert_eq!(
+            bracoxidize("x{a,b}-{1..2}y"),
+            Ok(vec![
+                "xa-1y".to_owned(),
+                "xa-2y".to_owned(),
+                "xb-1y".to_owned(),
+                "xb-2y".to_owned(),
+            ])
+        );
+        assert_eq!(
+            bracoxidize("{a}{b}}"),
+            Err(OxidizationError::ParsingError(
+                parser::ParsingError::ExtraCBra(6)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        for (content, depth) in [
+            ("{a,b}", 1),
+            ("{a,b}{c,d}{1..3}", 1),
+            ("x{a,b{c,d}}y", 2),
+            ("{a,{b,{c,d}}}", 3),
+            ("{a,{b,{1..3}}}{e,f}", 3),
+        ] {
+            let ast = parse(content);
+            assert_eq!(max_depth(&ast), depth, "{content}");
+        }
+    }
+
+    #[test]
+    fn test_numeric_prefix_and_postfix() {
+        assert_eq!(
+            bracoxidize("12{3..5}"),
+            Ok(vec!["123".to_owned(), "124".to_owned(), "125".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("{1..3}45"),
+            Ok(vec!["145".to_owned(), "245".to_owned(), "345".to_owned()])
+        );
+        assert_eq!(
+            bracoxidize("1.2{3..4}5"),
+            Ok(vec!["1.235".to_owned(), "1.245".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_oxidization_error() {
+        fn pipeline(content: &str) -> Result<usize, OxidizationError> {
+            let tokens = tokenizer::tokenize(content)?;
+            let ast = parser::parse(&tokens)?;
+            Ok(expand(&ast)?.len())
+        }
+        assert_eq!(pipeline("{a,b}{1..3}"), Ok(6));
+        let error = pipeline("}{").unwrap_err();
+        assert_eq!(
+            error,
+            OxidizationError::ParsingError(parser::ParsingError::ExtraCBra(0))
+        );
+        assert_eq!(
+            error.to_string(),
+            "Parsing failed: Used extra closing bracket at 0"
+        );
+        let error: Box<dyn std::error::Error> = Box::new(error);
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_bracoxidize_shell_quoted() {
+        assert_eq!(
+            bracoxidize_shell_quoted("{a b,c'd,}e$f"),
+            Ok(vec![
+                "'a be$f'".to_owned(),
+                "'c'\\''de$f'".to_owned(),
+                "'e$f'".to_owned()
+            ])
+        );
     }
 }
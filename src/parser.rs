@@ -25,8 +25,13 @@ use crate::tokenizer::Token;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
     /// Represents a text node.
-    /// It contains the text value and the starting position of the text.
-    Text { message: Arc<String>, start: usize },
+    /// It contains the text value, the starting position of the text, and the position right
+    /// after it. The text may be shorter than what it spans, as its escapes and quotes are gone.
+    Text {
+        message: Arc<String>,
+        start: usize,
+        end: usize,
+    },
     /// Represents a brace expansion node.
     /// It includes the prefix, inside, and outside node, along with the
     /// starting positions.
@@ -43,21 +48,267 @@ pub enum Node {
         start: usize,
         end: usize,
     },
+    /// Represents pipe seperated Nodes in braces, e.g. `{a|b}`, the alternatives of a
+    /// first-match group. It expands like a [Node::Collection], except in
+    /// [expand_first_match](crate::expand_first_match).
+    Alternation {
+        items: Vec<Node>,
+        start: usize,
+        end: usize,
+    },
     /// Represents a range node.
-    /// It contains the starting and ending numbers of the range, along with the
-    /// starting position.
+    /// It contains the starting and ending numbers of the range, whether the ending
+    /// number is included (`..`) or not (`..<`), along with the starting position.
+    ///
+    /// The limits are kept as written, e.g. `-01`, since the leading zeros decide how the
+    /// values are padded. Both limits may be written in hex instead, e.g. `{0x00..0xff}`,
+    /// then the values are written in hex too, padded to the digits of the wider limit.
+    ///
+    /// The optional sample count (e.g. `{0..100:~5}`) picks that many evenly spread values
+    /// instead of every value of the range. The optional step (e.g. `{1..10..2}`) picks every
+    /// step-th value instead, like bash. Only its magnitude matters, and a step of 0 is 1.
+    /// A step written after a colon has an explicit sign (e.g. `{10..1:-2}`), which decides
+    /// the direction instead of the order of the limits. It's kept with its sign, while the
+    /// sign of a bash-like step is dropped.
     Range {
         from: Arc<String>,
         to: Arc<String>,
+        inclusive: bool,
+        sample_count: Option<Arc<String>>,
+        step: Option<Arc<String>>,
         start: usize,
         end: usize,
     },
+    /// Represents a character range node, e.g. `{a..e}`.
+    /// It contains the starting and ending characters of the range, whether the
+    /// ending character is included (`..`) or not (`..<`), along with the
+    /// starting position.
+    ///
+    /// The optional case suffix converts the values, `:^` to uppercase (e.g. `{a..c:^}` yields
+    /// `A`, `B`, `C`) and `:_` to lowercase. The limits still decide which values there are.
+    CharRange {
+        from: char,
+        to: char,
+        inclusive: bool,
+        case: Option<Case>,
+        start: usize,
+        end: usize,
+    },
+    /// Represents an alphabetic range node, e.g. `{aa..az}`.
+    /// It contains the starting and ending words of the range, whether the ending
+    /// word is included (`..`) or not (`..<`), along with the starting position.
+    ///
+    /// Both words are ASCII letters of the same case and the same width (at least two),
+    /// counted like the columns of a spreadsheet: `az` is followed by `ba`.
+    AlphaRange {
+        from: Arc<String>,
+        to: Arc<String>,
+        inclusive: bool,
+        start: usize,
+        end: usize,
+    },
+}
+
+/// The case the values of a [Node::CharRange] are converted to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Case {
+    /// Converts the values to uppercase, written as the `:^` suffix.
+    Upper,
+    /// Converts the values to lowercase, written as the `:_` suffix.
+    Lower,
+}
+
+impl Node {
+    /// Returns the range of positions the node covers in the tokenized content.
+    ///
+    /// For brace groups ([Node::Collection], [Node::Range], [Node::CharRange],
+    /// [Node::AlphaRange]) the range includes both braces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::parser::parse;
+    /// use bracoxide::tokenizer::tokenize;
+    ///
+    /// let node = parse(&tokenize("ab{c,d}e").unwrap()).unwrap();
+    /// assert_eq!(node.span(), 0..8);
+    /// ```
+    pub fn span(&self) -> std::ops::Range<usize> {
+        match self {
+            Node::Text { start, end, .. } => *start..*end,
+            Node::BraceExpansion {
+                prefix,
+                inside,
+                postfix,
+                start,
+                end,
+            } => {
+                let end = [postfix, inside, prefix]
+                    .into_iter()
+                    .flatten()
+                    .map(|n| n.span().end)
+                    .next()
+                    .unwrap_or(*end);
+                *start..end
+            }
+            Node::Collection { start, end, .. }
+            | Node::Alternation { start, end, .. }
+            | Node::Range { start, end, .. }
+            | Node::CharRange { start, end, .. }
+            | Node::AlphaRange { start, end, .. } => *start..end.saturating_add(1),
+        }
+    }
+
+    /// Expands the items of the node's collection on their own, e.g. to list the alternatives
+    /// of a group in a chooser, rather than every combination of the pattern.
+    ///
+    /// The collection is the node itself, or the brace group of a [Node::BraceExpansion],
+    /// whose prefix and postfix are left out. Returns `None` if there is no such collection,
+    /// e.g. for a text or a range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::parser::parse;
+    /// use bracoxide::tokenizer::tokenize;
+    ///
+    /// let node = parse(&tokenize("x{a,b{c,d}}y").unwrap()).unwrap();
+    /// let items = node.collection_items_expanded().unwrap().unwrap();
+    /// assert_eq!(items, vec!["a", "bc", "bd"]);
+    /// ```
+    pub fn collection_items_expanded(&self) -> Option<Result<Vec<String>, crate::ExpansionError>> {
+        match self {
+            Node::Collection { .. } => Some(crate::expand(self)),
+            Node::BraceExpansion {
+                inside: Some(inside),
+                ..
+            } if matches!(**inside, Node::Collection { .. }) => Some(crate::expand(inside)),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the node as a pattern which tokenizes and parses back into the same structure, e.g.
+/// `a{b,c}{1..3}`. Texts are escaped where they would be read as braces, separators or range
+/// operators.
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Text { message, .. } => write_escaped(f, message),
+            Node::BraceExpansion {
+                prefix,
+                inside,
+                postfix,
+                ..
+            } => {
+                if let Some(prefix) = prefix {
+                    write!(f, "{prefix}")?;
+                }
+                match inside.as_deref() {
+                    // a text in braces, e.g. `{a}`, is still a brace group.
+                    Some(Node::Text { message, .. }) => {
+                        write!(f, "{{")?;
+                        write_escaped(f, message)?;
+                        write!(f, "}}")?;
+                    }
+                    Some(inside) => write!(f, "{inside}")?,
+                    None => (),
+                }
+                match postfix {
+                    Some(postfix) => write!(f, "{postfix}"),
+                    None => Ok(()),
+                }
+            }
+            Node::Collection { items, .. } | Node::Alternation { items, .. } => {
+                let separator = match self {
+                    Node::Alternation { .. } => "|",
+                    _ => ",",
+                };
+                write!(f, "{{")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "}}")
+            }
+            Node::Range {
+                from,
+                to,
+                inclusive,
+                sample_count,
+                step,
+                ..
+            } => {
+                let operator = if *inclusive { ".." } else { "..<" };
+                write!(f, "{{{from}{operator}{to}")?;
+                if let Some(count) = sample_count {
+                    write!(f, ":~{count}")?;
+                }
+                match step {
+                    Some(step) if step.starts_with(['+', '-']) => write!(f, ":{step}")?,
+                    Some(step) => write!(f, "..{step}")?,
+                    None => (),
+                }
+                write!(f, "}}")
+            }
+            Node::CharRange {
+                from,
+                to,
+                inclusive,
+                case,
+                ..
+            } => {
+                write!(f, "{{")?;
+                write_escaped(f, &from.to_string())?;
+                write!(f, "{}", if *inclusive { ".." } else { "..<" })?;
+                write_escaped(f, &to.to_string())?;
+                match case {
+                    Some(Case::Upper) => write!(f, ":^")?,
+                    Some(Case::Lower) => write!(f, ":_")?,
+                    None => (),
+                }
+                write!(f, "}}")
+            }
+            Node::AlphaRange {
+                from,
+                to,
+                inclusive,
+                ..
+            } => {
+                let operator = if *inclusive { ".." } else { "..<" };
+                write!(f, "{{{from}{operator}{to}}}")
+            }
+        }
+    }
+}
+
+/// Writes the text of a [Node::Text] so it is tokenized as text again: braces, separators and
+/// backslashes are escaped, and so is a dot starting a range operator (`..`).
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_escaped = match c {
+            '{' | '}' | ',' | '|' | '\\' => true,
+            '.' => chars.peek() == Some(&'.'),
+            _ => false,
+        };
+        if is_escaped {
+            write!(f, "\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    Ok(())
 }
 
 /// Represents an error that can occur during parsing.
 ///
 /// The `ParsingError` enum captures different error scenarios that can happen during parsing.
+/// More may be detected in a minor release, so the enum is non-exhaustive and a `match` on it
+/// needs a wildcard arm.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ParsingError {
     /// Indicates that there are no tokens to parse.
     NoTokens,
@@ -75,14 +326,22 @@ pub enum ParsingError {
     InvalidCommaUsage(usize),
     /// Extra Closing Brace, e.g. `{} }`
     ExtraCBra(usize),
-    /// Extra Opening Brace, e.g. `{3{..5}`
+    /// Extra Opening Brace, e.g. `{3{..5}`, or one which is never closed, e.g. `{a{b,c}`
     ExtraOBra(usize),
     /// Nothing in braces, e.g. `{}`
     NothingInBraces(usize),
-    /// Range can't have text in it.
+    /// Range can't have text in it, other than a single character or letters of the same
+    /// width on both ends, e.g. `{1..a}` or `{ab..c}`.
     RangeCantHaveText(usize),
-    /// Extra Range Operator have used, e.g. `{3..5..}`
+    /// Extra Range Operator have used, e.g. `{3..5..1..}`
     ExtraRangeOperator(usize),
+    /// Expected the sample count after the sample suffix, e.g. `{0..100:~}`
+    SampleCountExpected(usize),
+    /// Expected the step after the second range operator or the signed step suffix, e.g.
+    /// `{1..10..}` or `{10..1:-}`
+    StepExpected(usize),
+    /// Commas and pipes are used in the same group, e.g. `{a,b|c}`
+    MixedSeparators(usize),
 }
 
 impl std::fmt::Display for ParsingError {
@@ -107,11 +366,20 @@ impl std::fmt::Display for ParsingError {
             ),
             ParsingError::RangeCantHaveText(i) => write!(
                 f,
-                "Unrecognized char at {i}. Range syntax doesn't allow other than [0-9.]"
+                "Unrecognized char at {i}. Range limits must be numbers, single characters or letters of the same width."
             ),
             ParsingError::ExtraRangeOperator(i) => {
                 write!(f, "Extra range operator (..) used at {i}")
             }
+            ParsingError::SampleCountExpected(i) => {
+                write!(f, "Sample count not specified after the suffix (:~) at {i}")
+            }
+            ParsingError::StepExpected(i) => {
+                write!(f, "Range step not specified after the range operator (..) or sign (:+, :-) at {i}")
+            }
+            ParsingError::MixedSeparators(i) => {
+                write!(f, "Comma (,) and pipe (|) used in the same group at {i}")
+            }
         }
     }
 }
@@ -130,262 +398,467 @@ impl std::error::Error for ParsingError {}
 /// # Returns
 ///
 /// * `Result<Node, ParsingError>` - A result containing the parsed AST nodes or an error.
-pub fn parse(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
+pub fn parse(tokens: &[Token]) -> Result<Node, ParsingError> {
+    // nothing follows the last token, so its escapes and quotes can't be seen.
+    let end = tokens
+        .last()
+        .map_or(0, |t| t.position().saturating_add(t.width()));
+    parse_until(tokens, end)
+}
+
+/// Parses a sequence of tokens, followed by a token at `end`, see [parse].
+fn parse_until(tokens: &[Token], until: usize) -> Result<Node, ParsingError> {
     if tokens.is_empty() {
         return Err(ParsingError::NoTokens);
     }
-    match seperate(tokens) {
-        Ok(seperated) => {
-            let prefix = if let Some(prefix) = seperated.0 {
-                match text(&prefix) {
-                    Ok(n) => Some(Box::new(n)),
-                    Err(e) => return Err(e),
-                }
-            } else {
-                None
-            };
-            let inside = if let Some(inside) = seperated.1 {
-                match collection(&inside) {
-                    Ok(n) => Some(Box::new(n)),
-                    Err(e) => return Err(e),
-                }
-            } else {
-                None
-            };
-            let postfix = if let Some(postfix) = seperated.2 {
-                let parsed = if postfix
-                    .iter()
-                    .any(|t| matches!(t, Token::OBra(_) | Token::CBra(_)))
-                {
-                    parse(&postfix)
-                } else {
-                    text(&postfix)
-                };
-                match parsed {
-                    Ok(n) => Some(Box::new(n)),
-                    Err(e) => return Err(e),
-                }
-            } else {
-                None
-            };
-            let mut pos = (0_usize, 0_usize);
-            if let Some(token) = tokens.first() {
-                match token {
-                    Token::OBra(s)
-                    | Token::CBra(s)
-                    | Token::Comma(s)
-                    | Token::Text(_, s)
-                    | Token::Number(_, s)
-                    | Token::Range(s) => pos.0 = *s,
-                }
+    check_braces(tokens)?;
+    // Adjacent brace groups, e.g. `a{b,c}d{e,f}g`, are peeled one at a time from left to
+    // right, each one becoming the postfix of the previous one. Peeling them in a single pass
+    // rather than reparsing every postfix keeps long chains of groups linear.
+    let mut peeled = vec![];
+    let mut rest = tokens;
+    let postfix = loop {
+        let (prefix, inside, postfix) = seperate(rest)?;
+        let prefix = match prefix {
+            Some(prefix) => {
+                let end = inside
+                    .and_then(<[Token]>::first)
+                    .map_or(until, Token::position);
+                Some(Box::new(text(prefix, end)?))
             }
-            if let Some(token) = tokens.last() {
-                match token {
-                    Token::OBra(s) | Token::CBra(s) | Token::Comma(s) => pos.1 = *s,
-                    Token::Text(b, s) | Token::Number(b, s) => {
-                        pos.1 = if b.len() == 1 { *s } else { s + b.len() };
-                    }
-                    Token::Range(s) => pos.1 = s + 1,
-                }
+            None => None,
+        };
+        let inside = match inside {
+            Some(inside) => Some(Box::new(collection(inside)?)),
+            None => None,
+        };
+        peeled.push((prefix, inside, rest));
+        match postfix {
+            Some(postfix)
+                if postfix
+                    .iter()
+                    .any(|t| matches!(t, Token::OBra(_) | Token::CBra(_))) =>
+            {
+                rest = postfix
             }
-            Ok(Node::BraceExpansion {
+            Some(postfix) => break Some(Box::new(text(postfix, until)?)),
+            None => break None,
+        }
+    };
+    // position of the last char of the tokens.
+    let end = tokens.last().map_or(0, |t| {
+        t.position().saturating_add(t.width().saturating_sub(1))
+    });
+    let node = peeled
+        .into_iter()
+        .rev()
+        .fold(postfix, |postfix, (prefix, inside, rest)| {
+            Some(Box::new(Node::BraceExpansion {
                 prefix,
                 inside,
                 postfix,
-                start: pos.0,
-                end: pos.1,
-            })
+                start: rest.first().map_or(0, Token::position),
+                end,
+            }))
+        });
+    match node {
+        Some(node) => Ok(*node),
+        None => Err(ParsingError::NoTokens),
+    }
+}
+
+/// Checks that every brace is matched, and that no range operator is used before the first
+/// opening brace. A separator is literal there, see [text].
+///
+/// An opening brace which is never closed is reported as [ParsingError::ExtraOBra], pointing
+/// at the innermost one, e.g. `0` for `{a{b,c}`.
+fn check_braces(tokens: &[Token]) -> Result<(), ParsingError> {
+    // positions of the opening braces which aren't closed yet.
+    let mut opened = vec![];
+    let mut is_prefix = true;
+    for token in tokens {
+        match token {
+            Token::OBra(s) => {
+                opened.push(*s);
+                is_prefix = false;
+            }
+            Token::CBra(s) => {
+                opened.pop().ok_or(ParsingError::ExtraCBra(*s))?;
+            }
+            Token::Range(s) | Token::ExclusiveRange(s) if is_prefix => {
+                return Err(ParsingError::OBraExpected(*s));
+            }
+            _ => (),
         }
-        Err(e) => Err(e),
     }
+    match opened.last() {
+        Some(s) => Err(ParsingError::ExtraOBra(*s)),
+        None => Ok(()),
+    }
+}
+
+/// Returns `None` for an empty section.
+fn non_empty(tokens: &[Token]) -> Option<&[Token]> {
+    (!tokens.is_empty()).then_some(tokens)
 }
 
+/// Optional prefix, inside, and postfix token sections produced by [seperate].
+type Seperated<'a> = (
+    Option<&'a [Token]>,
+    Option<&'a [Token]>,
+    Option<&'a [Token]>,
+);
+
 /// Separates the given tokens into prefix, inside, and postfix sections based on the bracing structure.
 ///
+/// The inside section is the first brace group, the postfix section is left as is, even if it
+/// contains other brace groups.
+///
 /// # Arguments
 ///
-/// * `tokens` - A vector of tokens to be separated.
+/// * `tokens` - A slice of tokens to be separated.
 ///
 /// # Returns
 ///
-/// Returns a result containing tuples of optional vectors representing the prefix, inside, and
+/// Returns a result containing tuples of optional slices representing the prefix, inside, and
 /// postfix sections respectively. If the separation fails, a [ParsingError] is returned.
-fn seperate(
-    tokens: &Vec<Token>,
-) -> Result<(Option<Vec<Token>>, Option<Vec<Token>>, Option<Vec<Token>>), ParsingError> {
+fn seperate(tokens: &[Token]) -> Result<Seperated<'_>, ParsingError> {
     if tokens.is_empty() {
         return Err(ParsingError::NoTokens);
     }
-    #[derive(Debug, PartialEq)]
-    enum BracingState {
-        Prefix,
-        Inside,
-        Postfix,
-    }
-
     let mut count = (0_usize, 0_usize);
-    let mut inside_tokens = vec![];
-    let mut prefix_tokens = vec![];
-    let mut postfix_tokens = vec![];
-    let mut bracing_state = BracingState::Prefix;
-    for token in tokens {
+    // index of the opening brace of the first group.
+    let mut opening = None;
+    for (i, token) in tokens.iter().enumerate() {
         match token {
             Token::OBra(_) => {
                 count.0 += 1;
-                match bracing_state {
-                    BracingState::Prefix => {
-                        bracing_state = BracingState::Inside;
-                        inside_tokens.push(token.clone());
-                    }
-                    BracingState::Inside => inside_tokens.push(token.clone()),
-                    BracingState::Postfix => postfix_tokens.push(token.clone()),
-                }
+                opening.get_or_insert(i);
             }
             Token::CBra(s) => {
                 count.1 += 1;
-                if count.0 < count.1 {
-                    return Err(ParsingError::ExtraCBra(*s));
-                }
-                match bracing_state {
-                    BracingState::Prefix => return Err(ParsingError::ExtraCBra(*s)),
-                    BracingState::Inside => {
-                        inside_tokens.push(token.clone());
-                        if count.0 == count.1 {
-                            bracing_state = BracingState::Postfix;
-                        }
+                match opening {
+                    Some(opening) if count.0 == count.1 => {
+                        return Ok((
+                            non_empty(&tokens[..opening]),
+                            Some(&tokens[opening..=i]),
+                            non_empty(&tokens[i + 1..]),
+                        ));
                     }
-                    BracingState::Postfix => postfix_tokens.push(token.clone()),
+                    Some(_) => (),
+                    None => return Err(ParsingError::ExtraCBra(*s)),
                 }
             }
-            Token::Comma(s) | Token::Range(s) if bracing_state == BracingState::Prefix => {
+            Token::Range(s) | Token::ExclusiveRange(s) if opening.is_none() => {
                 return Err(ParsingError::OBraExpected(*s));
             }
-            _ => match bracing_state {
-                BracingState::Prefix => prefix_tokens.push(token.clone()),
-                BracingState::Inside => inside_tokens.push(token.clone()),
-                BracingState::Postfix => postfix_tokens.push(token.clone()),
-            },
+            _ => (),
         }
     }
-    let prefix = if prefix_tokens.is_empty() {
-        None
-    } else {
-        Some(prefix_tokens)
-    };
-    let inside = if inside_tokens.is_empty() {
-        None
-    } else {
-        Some(inside_tokens)
-    };
-    let postfix = if postfix_tokens.is_empty() {
-        None
-    } else {
-        Some(postfix_tokens)
-    };
-    Ok((prefix, inside, postfix))
+    // The group is never closed, or there is no group at all.
+    match opening {
+        Some(opening) => Ok((
+            non_empty(&tokens[..opening]),
+            Some(&tokens[opening..]),
+            None,
+        )),
+        None => Ok((Some(tokens), None, None)),
+    }
 }
 
 /// Parses a sequence of tokens into a text node.
 ///
+/// A separator is literal here, like in bash, e.g. the commas of `a,b{c,d}`, which expands to
+/// `a,bc` and `a,bd` just like `a\,b{c,d}`. Only a brace group's own separators split it.
+///
 /// # Arguments
 ///
 /// * `tokens` - A vector of tokens representing the text to be parsed.
+/// * `end` - The position of the token following the text.
 ///
 /// # Returns
 ///
 /// Returns a result containing a [Node] representing the parsed text. If the parsing fails,
 /// a [ParsingError] is returned.
-fn text(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
+fn text(tokens: &[Token], end: usize) -> Result<Node, ParsingError> {
     if tokens.is_empty() {
         return Err(ParsingError::NoTokens);
     }
+    // A single token's text can be shared instead of copied, which matters for huge literals.
+    if let [Token::Text(b, s) | Token::Number(b, s)] = tokens {
+        return Ok(Node::Text {
+            message: Arc::clone(b),
+            start: *s,
+            // an empty item of a group, e.g. the second one of `{a,}`, spans nothing.
+            end: if b.is_empty() { *s } else { end.max(*s) },
+        });
+    }
     let mut buffer = String::new();
     let mut iter = tokens.iter();
     let mut start = 0_usize;
     if let Some(token) = iter.next() {
         match token {
-            Token::OBra(s) | Token::CBra(s) | Token::Comma(s) | Token::Range(s) => {
+            Token::OBra(s) | Token::CBra(s) | Token::Range(s) | Token::ExclusiveRange(s) => {
                 return Err(ParsingError::ExpectedText(*s))
             }
-            Token::Text(b, s) | Token::Number(b, s) => {
-                buffer.push_str(b);
+            Token::Comma(s) | Token::Pipe(s) | Token::Text(_, s) | Token::Number(_, s) => {
+                buffer.push_str(token.render());
                 start = *s;
             }
         }
     }
     for token in iter {
         match token {
-            Token::OBra(s) | Token::CBra(s) | Token::Comma(s) | Token::Range(s) => {
+            Token::OBra(s) | Token::CBra(s) | Token::Range(s) | Token::ExclusiveRange(s) => {
                 return Err(ParsingError::ExpectedText(*s))
             }
-            Token::Text(b, _) | Token::Number(b, _) => buffer.push_str(b),
+            Token::Comma(_) | Token::Pipe(_) | Token::Text(..) | Token::Number(..) => {
+                buffer.push_str(token.render())
+            }
         }
     }
     Ok(Node::Text {
         message: Arc::new(buffer),
         start,
+        end: end.max(start),
     })
 }
 
 /// Parses a sequence of tokens into a range node.
 ///
+/// A limit may be made of several adjacent number tokens, which are joined, e.g. the tokenizer
+/// splits the `12` of `{1\`, a line break, and `2..14}` around the line continuation. Any
+/// other text within a numeric limit, even a space as in `{1 2..3}`, is reported as
+/// [ParsingError::RangeCantHaveText].
+///
 /// # Arguments
 ///
 /// * `tokens` - A vector of tokens representing the range to be parsed.
+/// * `end` - The position of the token following the range.
 ///
 /// # Returns
 ///
 /// Returns a result containing a [Node] representing the parsed range.
 /// If the parsing fails, a [ParsingError] is returned.
-fn range(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
+fn range(tokens: &[Token], end: usize) -> Result<Node, ParsingError> {
     if tokens.is_empty() {
         return Err(ParsingError::NoTokens);
     }
-    let mut limits = (String::new(), String::new());
+    // start, end, sample count, and step limits.
+    let mut limits = (String::new(), String::new(), String::new(), String::new());
     let mut is_start = true;
     let mut is_first = true;
     let mut count = 0_u8;
     let mut pos = (0_usize, 0_usize);
+    let mut inclusive = true;
+    // position of the first text token, if any.
+    let mut text = None;
+    // position of the sample suffix (`:~`), if any.
+    let mut sample = None;
+    // position of the range operator (or the colon) before the step, if any.
+    let mut step = None;
+    // sign of a step written after a colon, e.g. `{10..1:-2}`.
+    let mut sign = None;
+    // end position of the last token.
+    let mut last = 0_usize;
 
+    // A suffix right after a limit ending with a letter, e.g. `0xff:~3`, is in the same text
+    // token as the letters, so it's split off.
+    let mut split = Vec::with_capacity(tokens.len());
     for token in tokens {
+        match token {
+            Token::Text(b, s)
+                if b.len() > 2 && [":~", ":+", ":-"].iter().any(|x| b.ends_with(x)) =>
+            {
+                let (limit, suffix) = b.split_at(b.len() - 2);
+                split.push(Token::Text(Arc::new(limit.to_owned()), *s));
+                let s = s.saturating_add(limit.chars().count());
+                split.push(Token::Text(Arc::new(suffix.to_owned()), s));
+            }
+            token => split.push(token.clone()),
+        }
+    }
+    for token in &split {
         match token {
             Token::OBra(s) => return Err(ParsingError::ExtraOBra(*s)),
             Token::CBra(s) => return Err(ParsingError::ExtraCBra(*s)),
-            Token::Comma(s) => return Err(ParsingError::InvalidCommaUsage(*s)),
-            Token::Text(_, s) => return Err(ParsingError::RangeCantHaveText(*s)),
-            Token::Number(b, s) => {
+            Token::Comma(s) | Token::Pipe(s) => return Err(ParsingError::InvalidCommaUsage(*s)),
+            Token::Text(b, s)
+                if b.as_str() == ":~" && !is_start && sample.is_none() && step.is_none() =>
+            {
+                if limits.1.is_empty() {
+                    return Err(ParsingError::RangeEndLimitExpected(*s));
+                }
+                sample = Some(*s);
+                last = s.saturating_add(2);
+            }
+            Token::Text(b, s)
+                if matches!(b.as_str(), ":+" | ":-")
+                    && !is_start
+                    && sample.is_none()
+                    && step.is_none() =>
+            {
+                if limits.1.is_empty() {
+                    return Err(ParsingError::RangeEndLimitExpected(*s));
+                }
+                step = Some(*s);
+                sign = b.chars().nth(1);
+                last = s.saturating_add(2);
+            }
+            Token::Text(b, s) | Token::Number(b, s) => {
+                if text.is_none() && matches!(token, Token::Text(..)) {
+                    text = Some(*s);
+                }
                 if is_first {
                     pos.0 = *s;
                     is_first = false;
                 }
-                match is_start {
-                    true => limits.0.push_str(b),
-                    false => limits.1.push_str(b),
+                // the underscores grouping the digits of a number, e.g. `1_000`, are dropped.
+                let b = match token {
+                    Token::Number(..) => b.replace('_', ""),
+                    _ => b.to_string(),
+                };
+                match (is_start, sample, step) {
+                    (true, _, _) => limits.0.push_str(&b),
+                    (false, None, None) => limits.1.push_str(&b),
+                    (false, Some(_), _) => limits.2.push_str(&b),
+                    (false, None, Some(_)) => limits.3.push_str(&b),
                 }
+                last = s.saturating_add(token.width());
             }
-            Token::Range(e) => {
+            Token::Range(e) | Token::ExclusiveRange(e) => {
                 if is_first {
                     return Err(ParsingError::RangeStartLimitExpected(*e));
                 }
                 count += 1;
-                if count != 1 {
-                    return Err(ParsingError::ExtraRangeOperator(*e));
+                match (count, token) {
+                    (1, _) if sample.is_none() => (),
+                    // the second operator introduces the step, e.g. `{1..10..2}`.
+                    (2, Token::Range(_)) if sample.is_none() => {
+                        if limits.1.is_empty() {
+                            return Err(ParsingError::RangeEndLimitExpected(*e));
+                        }
+                        step = Some(*e);
+                        last = e.saturating_add(token.width());
+                        continue;
+                    }
+                    _ => return Err(ParsingError::ExtraRangeOperator(*e)),
                 }
                 pos.1 = *e;
                 is_start = false;
+                inclusive = matches!(token, Token::Range(_));
+                last = e.saturating_add(token.width());
             }
         }
     }
     if limits.1.is_empty() {
         return Err(ParsingError::RangeEndLimitExpected(pos.1));
     }
-    let len = limits.1.len();
+    if let Some(s) = sample {
+        if limits.2.is_empty() {
+            return Err(ParsingError::SampleCountExpected(s));
+        }
+    }
+    if let Some(s) = step {
+        if limits.3.is_empty() {
+            return Err(ParsingError::StepExpected(s));
+        }
+    }
+    // the case suffix of a char range, e.g. `{a..c:^}`, follows its single char end limit.
+    let case = [(":^", Case::Upper), (":_", Case::Lower)]
+        .into_iter()
+        .find(|(suffix, _)| {
+            limits.1.ends_with(suffix)
+                && limits.1.chars().count() == 3
+                && sample.is_none()
+                && step.is_none()
+        })
+        .map(|(_, case)| case);
+    if case.is_some() {
+        limits.1.truncate(limits.1.len() - 2);
+    }
+    let is_number = |limit: &str| {
+        let digits = limit.strip_prefix('-').unwrap_or(limit);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    // hex limits, e.g. `0xff`, are numbers too, but only if both limits are.
+    let is_hex = |limit: &str| {
+        let digits = limit.strip_prefix('-').unwrap_or(limit).strip_prefix("0x");
+        digits.is_some_and(|digits| {
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+    };
+    let is_numeric = (is_number(&limits.0) && is_number(&limits.1)
+        || is_hex(&limits.0) && is_hex(&limits.1))
+        && limits.2.bytes().all(|b| b.is_ascii_digit())
+        && (step.is_none() || is_number(&limits.3));
+    if is_numeric && case.is_some() {
+        // numbers have no case.
+        return Err(ParsingError::RangeCantHaveText(last.saturating_sub(2)));
+    }
+    let is_escaped = tokens
+        .iter()
+        .any(|t| matches!(t, Token::Text(b, _) if b.bytes().any(|b| b.is_ascii_digit())));
+    if is_numeric && is_escaped {
+        // Digits only come as text when they are escaped, e.g. `{\1..\3}`, which forces the
+        // whole range to be literal text.
+        return Ok(Node::Text {
+            message: Arc::new(tokens.iter().map(Token::render).collect()),
+            start: pos.0,
+            end: end.max(pos.0),
+        });
+    }
+    // The only other text a numeric range can have is the sign of a negative limit.
+    let text = text.filter(|_| !is_numeric);
+    if let Some(s) = text {
+        // Text is only allowed as a single non-digit char on both ends, e.g. `{a..z}`, or as
+        // letters of the same case and width, e.g. `{aa..zz}`.
+        let mut from = limits.0.chars();
+        let mut to = limits.1.chars();
+        let letters =
+            |case: fn(&u8) -> bool| limits.0.bytes().chain(limits.1.bytes()).all(|b| case(&b));
+        let is_alpha = limits.0.len() == limits.1.len()
+            && (letters(u8::is_ascii_lowercase) || letters(u8::is_ascii_uppercase));
+        return match (from.next(), from.next(), to.next(), to.next()) {
+            (Some(from), None, Some(to), None)
+                if !from.is_ascii_digit()
+                    && !to.is_ascii_digit()
+                    && sample.is_none()
+                    && step.is_none() =>
+            {
+                Ok(Node::CharRange {
+                    from,
+                    to,
+                    inclusive,
+                    case,
+                    start: pos.0.saturating_sub(1),
+                    // the closing brace comes right after the last token.
+                    end: last,
+                })
+            }
+            _ if is_alpha && sample.is_none() && step.is_none() => Ok(Node::AlphaRange {
+                from: Arc::new(limits.0),
+                to: Arc::new(limits.1),
+                inclusive,
+                start: pos.0.saturating_sub(1),
+                // the closing brace comes right after the last token.
+                end: last,
+            }),
+            _ => Err(ParsingError::RangeCantHaveText(s)),
+        };
+    }
     Ok(Node::Range {
         from: Arc::new(limits.0),
         to: Arc::new(limits.1),
-        start: pos.0 - 1,
-        // +1 for '.', +1 for `}`
-        end: pos.1 + 2 + len,
+        inclusive,
+        sample_count: sample.map(|_| Arc::new(limits.2)),
+        step: step.map(|_| match sign {
+            Some(sign) => Arc::new(format!("{sign}{}", limits.3)),
+            None => Arc::new(limits.3.trim_start_matches('-').to_owned()),
+        }),
+        start: pos.0.saturating_sub(1),
+        // the closing brace comes right after the last token.
+        end: last,
     })
 }
 
@@ -398,7 +871,7 @@ fn range(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
 /// # Returns
 ///
 /// Returns a result containing a [Node] representing the parsed collection. If the parsing fails, a [ParsingError] is returned.
-fn collection(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
+fn collection(tokens: &[Token]) -> Result<Node, ParsingError> {
     if tokens.is_empty() {
         return Err(ParsingError::NoTokens);
     }
@@ -407,23 +880,32 @@ fn collection(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
     // in the seperate function, we're dealing with `{}}` or `{{}`, no need to deal with it here.
     // count of OBra (`{`), CBra (`}`), and the seperator (`,`).
     let mut count = (0_usize, 0_usize, 0_usize);
-    let mut collections: Vec<Vec<Token>> = vec![];
+    // the items, along with the position of the separator or the brace following them.
+    let mut collections: Vec<(Vec<Token>, usize)> = vec![];
     let mut current = vec![];
+    // whether the items are seperated by pipes (`|`) rather than commas, once known.
+    let mut is_alternation = None;
     for token in tokens {
         match token {
-            Token::Comma(s) if count.0 == (count.1 + 1) => {
+            Token::Comma(s) | Token::Pipe(s) if count.0 == (count.1 + 1) => {
+                let is_pipe = matches!(token, Token::Pipe(_));
+                if *is_alternation.get_or_insert(is_pipe) != is_pipe {
+                    return Err(ParsingError::MixedSeparators(*s));
+                }
                 // increase the seperator count by 1.
                 count.2 += 1;
                 if current.is_empty() {
                     match collections.is_empty() {
                         true => current.push(Token::Text(Arc::new(String::new()), *s)),
                         // The previous token was comma.
-                        false => current.push(Token::Text(Arc::new(String::new()), s - 1)),
+                        false => {
+                            current.push(Token::Text(Arc::new(String::new()), s.saturating_sub(1)))
+                        }
                     }
                 }
                 // we dealt with if it's empty.
                 // so it can't be empty.
-                collections.push(current.clone());
+                collections.push((current.clone(), *s));
                 current.clear();
             }
             Token::Comma(_) => {
@@ -448,10 +930,17 @@ fn collection(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
             _ => current.push(token.clone()),
         }
     }
+    if current.is_empty() && count.2 == 0 {
+        // nothing but the braces, e.g. `{}`.
+        return Err(ParsingError::NothingInBraces(pos.0));
+    }
     if current.is_empty() && collections.len() == count.2 {
-        current.push(Token::Text(Arc::new(String::new()), pos.1 - 1));
+        current.push(Token::Text(
+            Arc::new(String::new()),
+            pos.1.saturating_sub(1),
+        ));
     }
-    collections.push(current);
+    collections.push((current, pos.1));
     match collections.len() {
         0 => Err(ParsingError::NothingInBraces(pos.0)),
         1 => {
@@ -461,10 +950,24 @@ fn collection(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
             // Check for `Token::Range(_)` exist or not
             // if not exist, then it's text, return text(&current)
             // if exist return range(&current)
-            let collection = &collections[0];
-            match collection.iter().any(|t| matches!(t, Token::Range(_))) {
-                true => range(collection),
-                false => text(collection),
+            let (collection, end) = &collections[0];
+            if collection
+                .iter()
+                .any(|t| matches!(t, Token::OBra(_) | Token::CBra(_)))
+            {
+                // redundant braces around a brace expansion, e.g. `{{a,b}}` or `{x{a,b}}`.
+                return Ok(Node::Collection {
+                    items: vec![parse_until(collection, *end)?],
+                    start: pos.0,
+                    end: pos.1,
+                });
+            }
+            match collection
+                .iter()
+                .any(|t| matches!(t, Token::Range(_) | Token::ExclusiveRange(_)))
+            {
+                true => range(collection, *end),
+                false => text(collection, *end),
             }
         }
         _ => {
@@ -474,24 +977,37 @@ fn collection(tokens: &Vec<Token>) -> Result<Node, ParsingError> {
             //  It is better to put this collection inside parse(&collection), but is it any good?
             // Return a collection.
             let mut parsed_collections = vec![];
-            for collection in collections {
+            for (collection, end) in collections {
                 if collection
                     .iter()
                     .any(|t| matches!(t, Token::OBra(_) | Token::CBra(_)))
                 {
-                    match parse(&collection) {
+                    match parse_until(&collection, end) {
                         Ok(n) => parsed_collections.push(n),
                         Err(e) => return Err(e),
                     }
+                } else if collection
+                    .iter()
+                    .any(|t| matches!(t, Token::Range(_) | Token::ExclusiveRange(_)))
+                {
+                    // an item of its own can be a range too, e.g. `{1..3,x}`.
+                    parsed_collections.push(range(&collection, end)?);
                 } else {
-                    parsed_collections.push(text(&collection)?);
+                    parsed_collections.push(text(&collection, end)?);
                 }
             }
-            Ok(Node::Collection {
-                items: parsed_collections,
-                start: pos.0,
-                end: pos.1,
-            })
+            match is_alternation {
+                Some(true) => Ok(Node::Alternation {
+                    items: parsed_collections,
+                    start: pos.0,
+                    end: pos.1,
+                }),
+                _ => Ok(Node::Collection {
+                    items: parsed_collections,
+                    start: pos.0,
+                    end: pos.1,
+                }),
+            }
         }
     }
 }
@@ -501,12 +1017,12 @@ mod tests {
     use std::sync::Arc;
 
     use super::*;
-    use crate::tokenizer::Token;
+    use crate::tokenizer::{tokenize, tokenize_with, Token, TokenizerOptions};
 
     #[test]
     fn test_feature_empty_collection_item_at_the_end() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Text(Arc::new("A".into()), 0),
                 Token::OBra(1),
                 Token::Text(Arc::new("B".into()), 2),
@@ -518,21 +1034,25 @@ mod tests {
             Ok(Node::BraceExpansion {
                 prefix: Some(Box::new(Node::Text {
                     message: Arc::new("A".into()),
-                    start: 0
+                    start: 0,
+                    end: 1
                 })),
                 inside: Some(Box::new(Node::Collection {
                     items: vec![
                         Node::Text {
                             message: Arc::new("B".into()),
-                            start: 2
+                            start: 2,
+                            end: 3
                         },
                         Node::Text {
                             message: Arc::new("C".into()),
-                            start: 4
+                            start: 4,
+                            end: 5
                         },
                         Node::Text {
                             message: Arc::new(String::new()),
-                            start: 5
+                            start: 5,
+                            end: 5
                         },
                     ],
                     start: 1,
@@ -548,7 +1068,7 @@ mod tests {
     #[test]
     fn test_feature_empty_collection_item_at_the_start() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Text(Arc::new("A".into()), 0),
                 Token::OBra(1),
                 Token::Comma(2),
@@ -560,21 +1080,25 @@ mod tests {
             Ok(Node::BraceExpansion {
                 prefix: Some(Box::new(Node::Text {
                     message: Arc::new("A".into()),
-                    start: 0
+                    start: 0,
+                    end: 1
                 })),
                 inside: Some(Box::new(Node::Collection {
                     items: vec![
                         Node::Text {
                             message: Arc::new(String::new()),
-                            start: 2
+                            start: 2,
+                            end: 2
                         },
                         Node::Text {
                             message: Arc::new("B".into()),
-                            start: 3
+                            start: 3,
+                            end: 4
                         },
                         Node::Text {
                             message: Arc::new("C".into()),
-                            start: 5
+                            start: 5,
+                            end: 6
                         },
                     ],
                     start: 1,
@@ -590,7 +1114,7 @@ mod tests {
     #[test]
     fn test_feature_empty_collection_item_in_the_middle() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Text(Arc::new("A".into()), 0),
                 Token::OBra(1),
                 Token::Text(Arc::new("B".into()), 2),
@@ -602,21 +1126,25 @@ mod tests {
             Ok(Node::BraceExpansion {
                 prefix: Some(Box::new(Node::Text {
                     message: Arc::new("A".into()),
-                    start: 0
+                    start: 0,
+                    end: 1
                 })),
                 inside: Some(Box::new(Node::Collection {
                     items: vec![
                         Node::Text {
                             message: Arc::new("B".into()),
-                            start: 2
+                            start: 2,
+                            end: 3
                         },
                         Node::Text {
                             message: Arc::new(String::new()),
-                            start: 3
+                            start: 3,
+                            end: 3
                         },
                         Node::Text {
                             message: Arc::new("C".into()),
-                            start: 5
+                            start: 5,
+                            end: 6
                         },
                     ],
                     start: 1,
@@ -632,7 +1160,7 @@ mod tests {
     #[test]
     fn test_really_complex() {
         assert_eq!(
-            parse(&vec![
+            parse(&[
                 Token::Text(Arc::new("A".into()), 0),
                 Token::OBra(1),
                 Token::Text(Arc::new("B".into()), 2),
@@ -663,28 +1191,33 @@ mod tests {
             Ok(Node::BraceExpansion {
                 prefix: Some(Box::new(Node::Text {
                     message: Arc::new("A".into()),
-                    start: 0
+                    start: 0,
+                    end: 1
                 })),
                 inside: Some(Box::new(Node::Collection {
                     items: vec![
                         Node::Text {
                             message: Arc::new("B".into()),
-                            start: 2
+                            start: 2,
+                            end: 3
                         },
                         Node::BraceExpansion {
                             prefix: Some(Box::new(Node::Text {
                                 message: Arc::new("C".into()),
-                                start: 4
+                                start: 4,
+                                end: 5
                             })),
                             inside: Some(Box::new(Node::Collection {
                                 items: vec![
                                     Node::Text {
                                         message: Arc::new("D".into()),
-                                        start: 6
+                                        start: 6,
+                                        end: 7
                                     },
                                     Node::Text {
                                         message: Arc::new("E".into()),
-                                        start: 8
+                                        start: 8,
+                                        end: 9
                                     },
                                 ],
                                 start: 5,
@@ -692,14 +1225,16 @@ mod tests {
                             })),
                             postfix: Some(Box::new(Node::Text {
                                 message: Arc::new("F".into()),
-                                start: 10
+                                start: 10,
+                                end: 11
                             })),
                             start: 4,
                             end: 10,
                         },
                         Node::Text {
                             message: Arc::new("G".into()),
-                            start: 12
+                            start: 12,
+                            end: 13
                         }
                     ],
                     start: 1,
@@ -708,17 +1243,20 @@ mod tests {
                 postfix: Some(Box::new(Node::BraceExpansion {
                     prefix: Some(Box::new(Node::Text {
                         message: Arc::new("H".into()),
-                        start: 14
+                        start: 14,
+                        end: 15
                     })),
                     inside: Some(Box::new(Node::Collection {
                         items: vec![
                             Node::Text {
                                 message: Arc::new("J".into()),
-                                start: 16
+                                start: 16,
+                                end: 17
                             },
                             Node::Text {
                                 message: Arc::new("K".into()),
-                                start: 18
+                                start: 18,
+                                end: 19
                             },
                         ],
                         start: 15,
@@ -727,11 +1265,15 @@ mod tests {
                     postfix: Some(Box::new(Node::BraceExpansion {
                         prefix: Some(Box::new(Node::Text {
                             message: Arc::new("L".into()),
-                            start: 20
+                            start: 20,
+                            end: 21
                         })),
                         inside: Some(Box::new(Node::Range {
                             from: Arc::new("3".into()),
                             to: Arc::new("5".into()),
+                            inclusive: true,
+                            sample_count: None,
+                            step: None,
                             start: 21,
                             end: 26
                         })),
@@ -747,4 +1289,375 @@ mod tests {
             })
         )
     }
+
+    #[test]
+    fn test_leading_brace() {
+        let text = |message: &str, start| Node::Text {
+            message: Arc::new(message.into()),
+            start,
+            end: start + message.chars().count(),
+        };
+        let node = parse(&tokenize("{a,b}").unwrap()).unwrap();
+        assert_eq!(
+            node,
+            Node::BraceExpansion {
+                prefix: None,
+                inside: Some(Box::new(Node::Collection {
+                    items: vec![text("a", 1), text("b", 3)],
+                    start: 0,
+                    end: 4
+                })),
+                postfix: None,
+                start: 0,
+                end: 4
+            }
+        );
+        assert_eq!(node.span(), 0..5);
+        let node = parse(&tokenize("{a,b}c").unwrap()).unwrap();
+        assert_eq!(node.span(), 0..6);
+        assert!(matches!(
+            node,
+            Node::BraceExpansion { prefix: None, postfix: Some(ref postfix), .. }
+                if **postfix == text("c", 5)
+        ));
+    }
+
+    #[test]
+    fn test_redundant_braces() {
+        let parse = |content| parse(&crate::tokenizer::tokenize(content).unwrap());
+        let text = |message: &str, start| Node::Text {
+            message: Arc::new(message.into()),
+            start,
+            end: start + message.chars().count(),
+        };
+        assert_eq!(
+            parse("{x{a,b}}"),
+            Ok(Node::BraceExpansion {
+                prefix: None,
+                inside: Some(Box::new(Node::Collection {
+                    items: vec![Node::BraceExpansion {
+                        prefix: Some(Box::new(text("x", 1))),
+                        inside: Some(Box::new(Node::Collection {
+                            items: vec![text("a", 3), text("b", 5)],
+                            start: 2,
+                            end: 6
+                        })),
+                        postfix: None,
+                        start: 1,
+                        end: 6
+                    }],
+                    start: 0,
+                    end: 7
+                })),
+                postfix: None,
+                start: 0,
+                end: 7
+            })
+        );
+    }
+
+    #[test]
+    fn test_alternation() {
+        let options = crate::tokenizer::TokenizerOptions::default().alternation(true);
+        let parse = |content| parse(&crate::tokenizer::tokenize_with(content, &options).unwrap());
+        let text = |message: &str, start| Node::Text {
+            message: Arc::new(message.into()),
+            start,
+            end: start + message.chars().count(),
+        };
+        assert_eq!(
+            parse("{a||b}"),
+            Ok(Node::BraceExpansion {
+                prefix: None,
+                inside: Some(Box::new(Node::Alternation {
+                    items: vec![text("a", 1), text("", 2), text("b", 4)],
+                    start: 0,
+                    end: 5
+                })),
+                postfix: None,
+                start: 0,
+                end: 5
+            })
+        );
+        assert_eq!(parse("{a,b|c}"), Err(ParsingError::MixedSeparators(4)));
+        assert_eq!(parse("{a|b,c}"), Err(ParsingError::MixedSeparators(4)));
+        // a pipe outside of braces is literal, like a comma.
+        assert_eq!(
+            parse("a|b{c}"),
+            Ok(Node::BraceExpansion {
+                prefix: Some(Box::new(text("a|b", 0))),
+                inside: Some(Box::new(text("c", 4))),
+                postfix: None,
+                start: 0,
+                end: 5
+            })
+        );
+        // separators of nested groups don't mix.
+        assert!(parse("{a|{b,c}}").is_ok());
+    }
+
+    #[test]
+    fn test_operator_width() {
+        let parse = |content| parse(&crate::tokenizer::tokenize(content).unwrap()).unwrap();
+        let spans = |content| {
+            let node = parse(content);
+            match &node {
+                Node::BraceExpansion {
+                    inside: Some(inside),
+                    ..
+                } => (inside.span(), node.span()),
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(spans("{1..5}"), (0..6, 0..6));
+        assert_eq!(spans("{1..<5}"), (0..7, 0..7));
+        assert_eq!(spans("{a..<e}"), (0..7, 0..7));
+        assert_eq!(spans("x{aa..<ac}"), (1..10, 0..10));
+    }
+
+    #[test]
+    fn test_text_span() {
+        let spans = |content, options: &TokenizerOptions| match parse(
+            &tokenize_with(content, options).unwrap(),
+        )
+        .unwrap()
+        {
+            Node::BraceExpansion {
+                prefix,
+                inside: Some(inside),
+                postfix,
+                ..
+            } => {
+                let items = match *inside {
+                    Node::Collection { items, .. } => items,
+                    inside => vec![inside],
+                };
+                [prefix.map(|p| *p)]
+                    .into_iter()
+                    .flatten()
+                    .chain(items)
+                    .chain(postfix.map(|p| *p))
+                    .map(|n| n.span())
+                    .collect::<Vec<_>>()
+            }
+            _ => unreachable!(),
+        };
+        let options = TokenizerOptions::default();
+        // the escapes are spanned, though they're gone from the text.
+        assert_eq!(spans("a\\,b{c,d}", &options), vec![0..4, 5..6, 7..8]);
+        assert_eq!(spans("{a\\,b,c}x", &options), vec![1..5, 6..7, 8..9]);
+        assert_eq!(spans("{\\1..\\3}", &options), vec![1..7]);
+        let options = TokenizerOptions::default().quotes(true);
+        assert_eq!(spans("'a,b'{c,\"d|e\"}", &options), vec![0..5, 6..7, 8..13]);
+    }
+
+    #[test]
+    fn test_display() {
+        for content in [
+            "a{b,c}d{e,f}g",
+            "{a}",
+            "{a,{b,c},}",
+            "{1..<9..4}{0..10:~3}{10..1:-2}",
+            "{a..e}{aa..az}",
+            "{a..c:^}{A..<C:_}",
+            "{a\\,b,c\\..d}",
+        ] {
+            let node = parse(&tokenize(content).unwrap()).unwrap();
+            assert_eq!(node.to_string(), content);
+        }
+        let options = TokenizerOptions::default().alternation(true);
+        let node = parse(&tokenize_with("{a|b\\|c}", &options).unwrap()).unwrap();
+        assert_eq!(node.to_string(), "{a|b\\|c}");
+    }
+
+    #[test]
+    fn test_inconsistent_positions() {
+        // tokens built by hand may have positions the tokenizer never produces, parsing them
+        // must not overflow.
+        let number = |n: &str, s| Token::Number(Arc::new(n.into()), s);
+        let inputs = [
+            vec![Token::Pipe(usize::MAX)],
+            vec![number("01", usize::MAX)],
+            vec![
+                Token::OBra(1),
+                Token::Comma(0),
+                Token::Comma(0),
+                Token::CBra(0),
+            ],
+            vec![
+                Token::OBra(0),
+                number("1", 0),
+                Token::Range(usize::MAX),
+                number("2", usize::MAX),
+                Token::Text(Arc::new(":+".into()), usize::MAX),
+                number("1", usize::MAX),
+                Token::CBra(0),
+            ],
+            vec![
+                Token::OBra(usize::MAX),
+                Token::Text(Arc::new("a".into()), usize::MAX),
+                Token::Comma(usize::MAX),
+                Token::CBra(usize::MAX),
+            ],
+        ];
+        for tokens in inputs {
+            let node = parse(&tokens).unwrap();
+            assert!(node.span().start <= node.span().end, "{tokens:?}");
+            assert!(crate::expand(&node).is_ok(), "{tokens:?}");
+        }
+    }
+
+    #[test]
+    fn test_hex_range() {
+        let parse = |content| parse(&tokenize(content).unwrap());
+        let Ok(Node::BraceExpansion {
+            inside: Some(inside),
+            ..
+        }) = parse("{0x00..0xff:~3}")
+        else {
+            panic!("a hex range is a brace expansion");
+        };
+        assert!(matches!(
+            *inside,
+            Node::Range { ref from, ref to, sample_count: Some(ref n), start: 0, end: 14, .. }
+                if **from == "0x00" && **to == "0xff" && **n == "3"
+        ));
+        assert!(matches!(
+            parse("{-0xA..0x1f:-2}"),
+            Ok(Node::BraceExpansion { inside: Some(inside), .. })
+                if matches!(*inside, Node::Range { ref step, .. } if step.as_deref().is_some_and(|s| *s == "-2"))
+        ));
+        // both limits are hex, or none.
+        assert_eq!(parse("{0x0..10}"), Err(ParsingError::RangeCantHaveText(2)));
+        assert_eq!(parse("{0xg..0x1}"), Err(ParsingError::RangeCantHaveText(2)));
+        assert_eq!(parse("{0x..0x1}"), Err(ParsingError::RangeCantHaveText(2)));
+    }
+
+    #[test]
+    fn test_ranges_in_collection() {
+        let Ok(Node::BraceExpansion {
+            inside: Some(inside),
+            ..
+        }) = parse(&tokenize("{0,2..5,8}").unwrap())
+        else {
+            panic!("a collection is a brace expansion");
+        };
+        let Node::Collection { items, .. } = *inside else {
+            panic!("the items are a collection");
+        };
+        // every item is classified on its own.
+        assert!(matches!(&items[0], Node::Text { message, start: 1, .. } if **message == "0"));
+        assert!(matches!(
+            &items[1],
+            Node::Range { from, to, start: 2, end: 7, .. } if **from == "2" && **to == "5"
+        ));
+        assert!(matches!(&items[2], Node::Text { message, start: 8, .. } if **message == "8"));
+    }
+
+    #[test]
+    fn test_collection_items_expanded() {
+        let items = |content| {
+            parse(&tokenize(content).unwrap())
+                .unwrap()
+                .collection_items_expanded()
+        };
+        let expected = |items: &[&str]| Some(Ok(items.iter().map(|s| s.to_string()).collect()));
+        assert_eq!(items("{a,b{c,d}}"), expected(&["a", "bc", "bd"]));
+        assert_eq!(
+            items("pre{a,{1..3},x{y,z}w,}post{e,f}"),
+            expected(&["a", "1", "2", "3", "xyw", "xzw", ""])
+        );
+        assert_eq!(items("{1..3}x"), None);
+        assert_eq!(items("{a}"), None);
+        let node = parse(&tokenize("{a,b}").unwrap()).unwrap();
+        let Node::BraceExpansion {
+            inside: Some(collection),
+            ..
+        } = node
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            collection.collection_items_expanded(),
+            expected(&["a", "b"])
+        );
+    }
+
+    #[test]
+    fn test_multi_token_limits() {
+        let number = |n: &str, s| Token::Number(Arc::new(n.into()), s);
+        let node = parse(&[
+            Token::OBra(0),
+            number("1", 1),
+            number("2", 2),
+            Token::Range(3),
+            number("-", 5),
+            number("3", 6),
+            number("4", 7),
+            Token::Text(Arc::new("..".into()), 8),
+            Token::Range(10),
+            number("0", 12),
+            number("2", 13),
+            Token::CBra(14),
+        ]);
+        // the `..` text isn't a range operator, so it can't be in a limit.
+        assert_eq!(node, Err(ParsingError::RangeCantHaveText(8)));
+        let node = parse(&[
+            Token::OBra(0),
+            number("1", 1),
+            number("2", 2),
+            Token::Range(3),
+            Token::Text(Arc::new("-".into()), 5),
+            number("3", 6),
+            number("4", 7),
+            Token::Range(8),
+            number("0", 10),
+            number("2", 11),
+            Token::CBra(12),
+        ]);
+        assert_eq!(
+            node,
+            Ok(Node::BraceExpansion {
+                prefix: None,
+                inside: Some(Box::new(Node::Range {
+                    from: Arc::new("12".into()),
+                    to: Arc::new("-34".into()),
+                    inclusive: true,
+                    sample_count: None,
+                    step: Some(Arc::new("02".into())),
+                    start: 0,
+                    end: 12
+                })),
+                postfix: None,
+                start: 0,
+                end: 12
+            })
+        );
+        let parse = |content| parse(&tokenize(content).unwrap());
+        assert_eq!(parse("{1 2..3}"), Err(ParsingError::RangeCantHaveText(2)));
+        assert_eq!(parse("{1..3 }"), Err(ParsingError::RangeCantHaveText(5)));
+        let Ok(Node::BraceExpansion {
+            inside: Some(inside),
+            ..
+        }) = parse("{1\\\n2..14}")
+        else {
+            panic!("the line continuation splits the limit");
+        };
+        assert!(
+            matches!(*inside, Node::Range { ref from, start: 0, end: 9, .. } if **from == "12")
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_braces() {
+        let parse = |content| parse(&crate::tokenizer::tokenize(content).unwrap());
+        assert_eq!(parse("{a{b,c}"), Err(ParsingError::ExtraOBra(0)));
+        assert_eq!(parse("{a,b{c}"), Err(ParsingError::ExtraOBra(0)));
+        assert_eq!(parse("{{a,b}"), Err(ParsingError::ExtraOBra(0)));
+        assert_eq!(parse("x{a{b,c}d"), Err(ParsingError::ExtraOBra(1)));
+        assert_eq!(parse("{a}{b"), Err(ParsingError::ExtraOBra(3)));
+        assert_eq!(parse("{a{b{c}"), Err(ParsingError::ExtraOBra(2)));
+        assert_eq!(parse("{a,b}c}"), Err(ParsingError::ExtraCBra(6)));
+        assert_eq!(parse("{a,b}}{c}"), Err(ParsingError::ExtraCBra(5)));
+    }
 }
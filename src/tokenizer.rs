@@ -20,6 +20,12 @@
 //! The [Token] enum represents different types of tokens, such as opening braces, closing braces,
 //! commas, text, numbers, and ranges. Each variant of the enum provides additional information
 //! related to the token, such as the position of the token in the input string.
+//!
+//! ## Stability
+//!
+//! [Token], [TokenizationError] and the functions of this module are part of the public API,
+//! so tools can consume the token stream directly. The state kept while tokenizing (the
+//! escape flag and the text/number buffers) is an implementation detail and stays private.
 
 use std::sync::Arc;
 
@@ -31,7 +37,9 @@ use std::sync::Arc;
 /// type of token, such as opening brace, closing brace, comma, text, number, or range
 /// operator.
 ///
-#[derive(Debug, PartialEq, Clone)]
+/// Tokens can be used as keys of a `HashMap` or `HashSet`, and sorted. Sorting orders them
+/// by kind first, in the order of the variants, then by their payload.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
 pub enum Token {
     /// Represents an opening brace `{` at the specified position.
     OBra(usize),
@@ -45,14 +53,112 @@ pub enum Token {
     Text(Arc<String>, usize),
     /// Represents a number at the specified position.
     ///
-    /// The associated `String` contains the numeric value.
+    /// The associated `String` contains the numeric value, as written. Its digits may be grouped
+    /// with underscores, e.g. `1_000`.
     Number(Arc<String>, usize),
     /// Represents the range operator `..` at the specified position.
     Range(usize),
+    /// Represents the exclusive range operator `..<` at the specified position.
+    ExclusiveRange(usize),
+    /// Represents a pipe `|` separating the alternatives of a first-match group at the
+    /// specified position, see [TokenizerOptions::alternation].
+    Pipe(usize),
+}
+
+impl Token {
+    /// Returns the position of the token in the input string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::tokenizer::tokenize;
+    ///
+    /// let tokens = tokenize("a{1..3}").unwrap();
+    /// let positions: Vec<usize> = tokens.iter().map(|t| t.position()).collect();
+    /// assert_eq!(positions, vec![0, 1, 2, 3, 5, 6]);
+    /// ```
+    pub fn position(&self) -> usize {
+        match self {
+            Token::OBra(s)
+            | Token::CBra(s)
+            | Token::Comma(s)
+            | Token::Text(_, s)
+            | Token::Number(_, s)
+            | Token::Range(s)
+            | Token::ExclusiveRange(s)
+            | Token::Pipe(s) => *s,
+        }
+    }
+
+    /// Returns the literal the token stands for, so the input can be rebuilt from the tokens.
+    ///
+    /// Text and numbers render as their value, which no longer contains the escape
+    /// characters, quotes or line continuations the tokenizer consumed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::tokenizer::tokenize;
+    ///
+    /// let tokens = tokenize("a{1..<3,b}").unwrap();
+    /// let rendered: String = tokens.iter().map(|t| t.render()).collect();
+    /// assert_eq!(rendered, "a{1..<3,b}");
+    /// ```
+    pub fn render(&self) -> &str {
+        match self {
+            Token::OBra(_) => "{",
+            Token::CBra(_) => "}",
+            Token::Comma(_) => ",",
+            Token::Text(b, _) | Token::Number(b, _) => b,
+            Token::Range(_) => "..",
+            Token::ExclusiveRange(_) => "..<",
+            Token::Pipe(_) => "|",
+        }
+    }
+
+    /// Returns the count of chars of the rendered token, see [Token::render]. This is the
+    /// width the token is written with only for the operators, braces and separators, e.g.
+    /// `..<` is 3 chars wide. Text and numbers render without the escapes, quotes and line
+    /// continuations they were written with, so they may be narrower than in the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bracoxide::tokenizer::tokenize;
+    ///
+    /// let tokens = tokenize("{1..<3}").unwrap();
+    /// assert_eq!(tokens[2].width(), 3);
+    /// assert_eq!(tokens[2].position() + tokens[2].width(), tokens[3].position());
+    ///
+    /// // `a\,b` is written with 4 chars, but renders as `a,b`.
+    /// let tokens = tokenize("a\\,b{c,d}").unwrap();
+    /// assert_eq!(tokens[0].width(), 3);
+    /// assert_eq!(tokens[1].position(), 4);
+    /// ```
+    pub fn width(&self) -> usize {
+        self.render().chars().count()
+    }
+
+    /// Returns a mutable reference to the position of the token.
+    fn position_mut(&mut self) -> &mut usize {
+        match self {
+            Token::OBra(s)
+            | Token::CBra(s)
+            | Token::Comma(s)
+            | Token::Text(_, s)
+            | Token::Number(_, s)
+            | Token::Range(s)
+            | Token::ExclusiveRange(s)
+            | Token::Pipe(s) => s,
+        }
+    }
 }
 
 /// Represents the possible errors that can occur during the tokenization.
 ///
+/// The enum is non-exhaustive, a `match` on it needs a wildcard arm for the variants added in
+/// later releases.
+///
 /// # Example
 ///
 /// ```rust
@@ -63,6 +169,7 @@ pub enum Token {
 /// assert_eq!(tokenization_result, Err(TokenizationError::FormatNotSupported));
 /// ```
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum TokenizationError {
     /// The content to be tokenized is empty.
     EmptyContent,
@@ -71,6 +178,12 @@ pub enum TokenizationError {
     FormatNotSupported,
     /// The input content does not contain any braces.
     NoBraces,
+    /// A quote opened at the given position is never closed, e.g. `'{a,b}`. Only reported
+    /// when quoting is enabled, see [TokenizerOptions::quotes].
+    UnclosedQuote(usize),
+    /// The content has more tokens than the given maximum, see
+    /// [TokenizerOptions::max_tokens].
+    TooManyTokens(usize),
 }
 
 impl std::fmt::Display for TokenizationError {
@@ -81,6 +194,10 @@ impl std::fmt::Display for TokenizationError {
                 write!(f, "Only opening brace or closing brace is used.")
             }
             TokenizationError::NoBraces => write!(f, "No braces have been used."),
+            TokenizationError::UnclosedQuote(i) => write!(f, "Quote at {i} is never closed."),
+            TokenizationError::TooManyTokens(max) => {
+                write!(f, "Content has more than {max} tokens.")
+            }
         }
     }
 }
@@ -134,48 +251,248 @@ impl std::error::Error for TokenizationError {}
 ///
 /// In this example, the `tokenize` function from the `bracoxide` crate is used to tokenize the content string "{1, 2, 3}".
 /// If the tokenization is successful, the resulting tokens are printed. Otherwise, the corresponding error is displayed.
-pub fn tokenize(content: &str) -> Result<Vec<Token>, TokenizationError> {
+///
+/// The content can be anything which borrows as a `str`, e.g. a `&String`, a `&Cow<str>` or a
+/// `&Box<str>`, so generic code needs no `.as_ref()` calls.
+pub fn tokenize(content: &(impl AsRef<str> + ?Sized)) -> Result<Vec<Token>, TokenizationError> {
+    tokenize_with(content.as_ref(), &TokenizerOptions::default())
+}
+
+/// Options controlling how [tokenize_with] tokenizes the content.
+///
+/// The default options tokenize exactly like [tokenize].
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TokenizerOptions {
+    quotes: bool,
+    empty_braces: bool,
+    alternation: bool,
+    literal_backslash: bool,
+    verbatim_groups: bool,
+    base: usize,
+    max_tokens: Option<usize>,
+}
+
+impl TokenizerOptions {
+    /// Enables shell-like quoting. Disabled by default, quotes are ordinary text then.
+    ///
+    /// When enabled, the quotes themselves are dropped and:
+    /// - Everything between single quotes is literal text, e.g. `'{a,b}'` is `{a,b}`.
+    /// - Braces, commas and ranges between double quotes still work, e.g. `"{a,b}"` expands
+    ///   to `a` and `b`. A backslash only escapes `"`, `\`, `{`, `}` and `,` there, before
+    ///   any other character it is kept as is, e.g. `"a\b"` is `a\b`.
+    pub fn quotes(mut self, quotes: bool) -> Self {
+        self.quotes = quotes;
+        self
+    }
+
+    /// Tokenizes empty braces `{}` as a group with a single empty item, so they vanish from
+    /// the expansion, e.g. `a{}b` expands to `ab`. Disabled by default, parsing empty braces
+    /// fails with [ParsingError::NothingInBraces](crate::parser::ParsingError::NothingInBraces)
+    /// then.
+    pub fn empty_braces(mut self, empty_braces: bool) -> Self {
+        self.empty_braces = empty_braces;
+        self
+    }
+
+    /// Tokenizes `|` as a [Token::Pipe], separating the alternatives of a first-match group,
+    /// e.g. `{a|b}`, see [expand_first_match](crate::expand_first_match). Disabled by default,
+    /// `|` is ordinary text then. An escaped pipe (`\|`) is always literal.
+    pub fn alternation(mut self, alternation: bool) -> Self {
+        self.alternation = alternation;
+        self
+    }
+
+    /// Tokenizes `\` as ordinary text rather than an escape, so Windows paths such as
+    /// `C:\Users\{alice,bob}` expand naturally. Disabled by default.
+    ///
+    /// Braces, commas and the other special chars can't be escaped then, but they can still
+    /// be quoted when [quotes](TokenizerOptions::quotes) is enabled, e.g. `C:\'{a}'\{b,c}`.
+    pub fn literal_backslash(mut self, literal_backslash: bool) -> Self {
+        self.literal_backslash = literal_backslash;
+        self
+    }
+
+    /// Keeps a brace group marked with a leading `%` as is, e.g. `%{a,b}` is the text `{a,b}`,
+    /// without escaping its chars. Disabled by default, `%` is ordinary text then.
+    ///
+    /// The marked group runs up to its matching closing brace, so the groups nested in it are
+    /// kept too, e.g. `%{a,{b,c}}` is `{a,{b,c}}`. Its escapes are kept as is, but an escaped
+    /// brace doesn't close it. An escaped marker (`\%`) is literal, the group after it expands.
+    pub fn verbatim_groups(mut self, verbatim_groups: bool) -> Self {
+        self.verbatim_groups = verbatim_groups;
+        self
+    }
+
+    /// Offsets the positions of the tokens, and of [TokenizationError::UnclosedQuote], by
+    /// `base`. 0 by default.
+    ///
+    /// Useful when the content is a fragment of a larger document, e.g. a pattern extracted by
+    /// an editor, so the spans of the parsed nodes point into the document, like [rebase].
+    ///
+    /// ```
+    /// use bracoxide::tokenizer::{tokenize_with, Token, TokenizerOptions};
+    ///
+    /// let document = "mkdir {a,b}";
+    /// let options = TokenizerOptions::default().base(6);
+    /// let tokens = tokenize_with(&document[6..], &options).unwrap();
+    /// assert_eq!(tokens[0], Token::OBra(6));
+    /// ```
+    pub fn base(mut self, base: usize) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Fails with [TokenizationError::TooManyTokens] as soon as the content yields more than
+    /// `max` tokens, e.g. to bound the memory spent on untrusted input before it is parsed.
+    /// Unlimited by default.
+    pub fn max_tokens(mut self, max: usize) -> Self {
+        self.max_tokens = Some(max);
+        self
+    }
+}
+
+/// Tokenizes the provided content like [tokenize], using the provided [TokenizerOptions].
+///
+/// # Errors
+///
+/// Returns the same errors as [tokenize], plus [TokenizationError::UnclosedQuote] when quoting
+/// is enabled and a quote is never closed.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::tokenizer::{tokenize_with, Token, TokenizerOptions};
+///
+/// let options = TokenizerOptions::default().quotes(true);
+/// let tokens = tokenize_with("'{a}'{b,c}", &options).unwrap();
+/// assert_eq!(tokens[0], Token::Text("{a}".to_owned().into(), 0));
+/// ```
+pub fn tokenize_with(
+    content: &str,
+    options: &TokenizerOptions,
+) -> Result<Vec<Token>, TokenizationError> {
     if content.is_empty() {
         return Err(TokenizationError::EmptyContent);
     }
     let mut tokens = Vec::<Token>::new();
     let mut is_escape = false;
+    // the opening quote and its position, if in a quoted part.
+    let mut quote: Option<(char, usize)> = None;
     // opening, closing
     let mut count = (0_usize, 0_usize);
     // text_buffer, number_buffer
     let mut buffers = (String::new(), String::new());
+    // position of the first char of the pending text, which may be a dropped one like a quote
+    // or a backslash, so the text's position points at where it is written.
+    let mut text_start: Option<usize> = None;
     let mut iter = content.chars().enumerate();
-    let tokenize_text_buffer = |tokens: &mut Vec<Token>, buffers: &mut (String, String), i| {
+    let tokenize_text_buffer = |tokens: &mut Vec<Token>,
+                                buffers: &mut (String, String),
+                                text_start: &mut Option<usize>,
+                                i| {
+        let start = text_start.take();
         if !buffers.0.is_empty() {
-            tokens.push(Token::Text(Arc::new(buffers.0.clone()), i - buffers.0.len()));
-            buffers.0.clear();
+            // positions are char indices, the text may hold multi-byte chars.
+            let start = start.unwrap_or(i - buffers.0.chars().count());
+            // take the buffer rather than cloning it, it may hold a huge literal.
+            tokens.push(Token::Text(Arc::new(std::mem::take(&mut buffers.0)), start));
         }
     };
     let tokenize_number_buffer = |tokens: &mut Vec<Token>, buffers: &mut (String, String), i| {
         if !buffers.1.is_empty() {
-            tokens.push(Token::Number(Arc::new(buffers.1.clone()), i - buffers.1.len()));
-            buffers.1.clear();
+            let start = i - buffers.1.len();
+            // take the buffer rather than cloning it, it may hold a huge literal.
+            tokens.push(Token::Number(
+                Arc::new(std::mem::take(&mut buffers.1)),
+                start,
+            ));
         }
     };
     // Push buffers into tokens.
-    let tokenize_buffers = |tokens: &mut Vec<Token>, buffers: &mut (String, String), i| {
-        tokenize_text_buffer(tokens, buffers, i);
+    let tokenize_buffers = |tokens: &mut Vec<Token>,
+                            buffers: &mut (String, String),
+                            text_start: &mut Option<usize>,
+                            i| {
+        tokenize_text_buffer(tokens, buffers, text_start, i);
         tokenize_number_buffer(tokens, buffers, i);
     };
+    let check_len = |tokens: &Vec<Token>| match options.max_tokens {
+        Some(max) if tokens.len() > max => Err(TokenizationError::TooManyTokens(max)),
+        _ => Ok(()),
+    };
     while let Some((i, c)) = iter.next() {
+        // every char adds a few tokens at most, so checking once per char stops early.
+        check_len(&tokens)?;
+        if options.quotes {
+            match (c, is_escape, quote) {
+                // Single quoted, everything is literal until the closing quote.
+                ('\'', _, Some(('\'', _))) => {
+                    quote = None;
+                    continue;
+                }
+                (_, _, Some(('\'', _))) => {
+                    tokenize_number_buffer(&mut tokens, &mut buffers, i);
+                    text_start.get_or_insert(i);
+                    buffers.0.push(c);
+                    continue;
+                }
+                // Double quoted, a backslash only escapes a few characters.
+                (_, true, Some(('"', _))) if !matches!(c, '"' | '\\' | '{' | '}' | ',') => {
+                    tokenize_number_buffer(&mut tokens, &mut buffers, i);
+                    text_start.get_or_insert(i - 1);
+                    buffers.0.push('\\');
+                    is_escape = false;
+                }
+                ('"', false, Some(('"', _))) => {
+                    quote = None;
+                    continue;
+                }
+                ('"' | '\'', false, None) => {
+                    text_start.get_or_insert(i);
+                    quote = Some((c, i));
+                    continue;
+                }
+                _ => (),
+            }
+        }
         match (c, is_escape) {
+            // A backslash right before a line break continues the line, both are dropped. A
+            // pending number ends right before the backslash, the rest of it is another token.
+            ('\n', true) => {
+                tokenize_number_buffer(&mut tokens, &mut buffers, i - 1);
+                is_escape = false;
+            }
+            ('\r', true) if matches!(iter.clone().next(), Some((_, '\n'))) => {
+                tokenize_number_buffer(&mut tokens, &mut buffers, i - 1);
+                iter.next();
+                is_escape = false;
+            }
             (_, true) => {
+                // the pending number ends right before the backslash.
+                tokenize_number_buffer(&mut tokens, &mut buffers, i - 1);
+                text_start.get_or_insert(i - 1);
                 buffers.0.push(c);
-                buffers.1.clear();
                 is_escape = false;
             }
-            ('\\', false) => is_escape = true,
+            ('\\', false) if !options.literal_backslash => is_escape = true,
+            // `%{` marks a brace group to keep as is, braces included, without its `%`.
+            ('%', false)
+                if options.verbatim_groups && matches!(iter.clone().next(), Some((_, '{'))) =>
+            {
+                tokenize_number_buffer(&mut tokens, &mut buffers, i);
+                text_start.get_or_insert(i);
+                if push_verbatim_group(&mut iter, &mut buffers.0, options) {
+                    // the group is text, but the content has braces, so it isn't rejected.
+                    count.0 += 1;
+                    count.1 += 1;
+                }
+            }
             // @1: COMMENT
             // Look it is '{' OR '}' OR ','
             // No other c value can pass this match ARM
             // And now look to @2
             ('{' | '}' | ',', _) => {
-                tokenize_buffers(&mut tokens, &mut buffers, i);
+                tokenize_buffers(&mut tokens, &mut buffers, &mut text_start, i);
                 match c {
                     '{' => {
                         count.0 += 1;
@@ -183,6 +500,9 @@ pub fn tokenize(content: &str) -> Result<Vec<Token>, TokenizationError> {
                     }
                     '}' => {
                         count.1 += 1;
+                        if options.empty_braces && matches!(tokens.last(), Some(Token::OBra(_))) {
+                            tokens.push(Token::Text(Arc::new(String::new()), i));
+                        }
                         tokens.push(Token::CBra(i));
                     }
                     ',' => tokens.push(Token::Comma(i)),
@@ -194,44 +514,295 @@ pub fn tokenize(content: &str) -> Result<Vec<Token>, TokenizationError> {
                     _ => unreachable!(),
                 }
             }
+            ('|', _) if options.alternation => {
+                tokenize_buffers(&mut tokens, &mut buffers, &mut text_start, i);
+                tokens.push(Token::Pipe(i));
+            }
             ('.', _) => {
                 let mut r_iter = iter.clone();
                 if let Some((_ix, cx)) = r_iter.next() {
                     match cx {
                         '.' => {
-                            tokenize_buffers(&mut tokens, &mut buffers, i);
-                            tokens.push(Token::Range(i));
-                            iter = r_iter;
+                            tokenize_buffers(&mut tokens, &mut buffers, &mut text_start, i);
+                            // `..<` is the exclusive variant of `..`
+                            let mut x_iter = r_iter.clone();
+                            if let Some((_, '<')) = x_iter.next() {
+                                tokens.push(Token::ExclusiveRange(i));
+                                iter = x_iter;
+                            } else {
+                                tokens.push(Token::Range(i));
+                                iter = r_iter;
+                            }
                             continue;
                         }
                         _ => {
                             tokenize_number_buffer(&mut tokens, &mut buffers, i);
+                            text_start.get_or_insert(i);
                             buffers.0.push(c);
                         }
                     }
                 } else {
+                    text_start.get_or_insert(i);
                     buffers.0.push(c);
                 }
             }
             ('0'..='9', _) => {
-                tokenize_text_buffer(&mut tokens, &mut buffers, i);
+                tokenize_text_buffer(&mut tokens, &mut buffers, &mut text_start, i);
+                buffers.1.push(c);
+            }
+            // an underscore between digits groups them, e.g. `1_000`, like in Rust literals.
+            ('_', false)
+                if !buffers.1.is_empty() && matches!(iter.clone().next(), Some((_, '0'..='9'))) =>
+            {
                 buffers.1.push(c);
             }
             _ => {
                 tokenize_number_buffer(&mut tokens, &mut buffers, i);
+                text_start.get_or_insert(i);
                 buffers.0.push(c);
             }
         }
     }
+    if let Some((_, i)) = quote {
+        return Err(TokenizationError::UnclosedQuote(
+            options.base.saturating_add(i),
+        ));
+    }
     match count {
         (0, 0) => return Err(TokenizationError::NoBraces),
         (0, _) | (_, 0) => return Err(TokenizationError::FormatNotSupported),
         (_, _) => (),
     }
-    tokenize_buffers(&mut tokens, &mut buffers, content.len());
+    let end = content.chars().count();
+    tokenize_buffers(&mut tokens, &mut buffers, &mut text_start, end);
+    check_len(&tokens)?;
+    rebase(&mut tokens, options.base);
     Ok(tokens)
 }
 
+/// Pushes the brace group starting at the next char of `iter` into `buffer` as is, up to its
+/// matching closing brace, and returns whether the group is closed. Escaped braces don't
+/// count, unless backslashes are literal.
+fn push_verbatim_group(
+    iter: &mut std::iter::Enumerate<std::str::Chars>,
+    buffer: &mut String,
+    options: &TokenizerOptions,
+) -> bool {
+    let (mut depth, mut escaped) = (0_usize, false);
+    for (_, c) in iter.by_ref() {
+        buffer.push(c);
+        match (c, escaped) {
+            (_, true) => escaped = false,
+            ('\\', false) if !options.literal_backslash => escaped = true,
+            ('{', false) => depth += 1,
+            ('}', false) => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Strips the comments from the provided content, before it is tokenized with the provided
+/// [TokenizerOptions].
+///
+/// A comment starts with `#` and runs to the end of the line, the line break itself is kept.
+/// An escaped `#` (`\#`) is literal, and so is a quoted one when quoting is enabled.
+///
+/// Returns the content without the comments, and where they were, see [unstrip_positions].
+pub(crate) fn strip_comments(
+    content: &str,
+    options: &TokenizerOptions,
+) -> (String, Vec<(usize, usize)>) {
+    let mut stripped = String::with_capacity(content.len());
+    // for every comment, its position in the stripped content (offset by the base like the
+    // tokens), and the count of chars stripped up to its end.
+    let mut removed = vec![];
+    let (mut kept, mut dropped) = (0_usize, 0_usize);
+    let mut is_escape = false;
+    let mut quote: Option<char> = None;
+    let mut iter = content.chars().peekable();
+    while let Some(c) = iter.next() {
+        match (c, is_escape, quote) {
+            (_, true, _) => is_escape = false,
+            ('\\', false, q) if q != Some('\'') && !options.literal_backslash => is_escape = true,
+            ('\'' | '"', false, None) if options.quotes => quote = Some(c),
+            (_, false, Some(q)) if q == c => quote = None,
+            ('#', false, None) => {
+                dropped += 1;
+                while let Some(&c) = iter.peek() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                    iter.next();
+                    dropped += 1;
+                }
+                removed.push((options.base.saturating_add(kept), dropped));
+                continue;
+            }
+            _ => (),
+        }
+        stripped.push(c);
+        kept += 1;
+    }
+    (stripped, removed)
+}
+
+/// Moves the positions of `tokens`, tokenized from the content [strip_comments] returned,
+/// back to where they are in the content with the comments it `removed`, so the errors point
+/// into the content as written.
+pub(crate) fn unstrip_positions(tokens: &mut [Token], removed: &[(usize, usize)]) {
+    for token in tokens {
+        let position = token.position_mut();
+        *position = unstrip_position(*position, removed);
+    }
+}
+
+/// Moves a single position like [unstrip_positions].
+pub(crate) fn unstrip_position(position: usize, removed: &[(usize, usize)]) -> usize {
+    match removed.partition_point(|&(at, _)| at <= position) {
+        0 => position,
+        n => position.saturating_add(removed[n - 1].1),
+    }
+}
+
+/// Shifts the position of every token in `tokens` by `offset`.
+///
+/// Useful when the tokens were produced from a fragment of a larger input and their
+/// positions need to point into that larger input instead. A position which would overflow
+/// stays at `usize::MAX`.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::tokenizer::{rebase, tokenize, Token};
+///
+/// let mut tokens = tokenize("{a,b}").unwrap();
+/// rebase(&mut tokens, 3);
+/// assert_eq!(tokens[0], Token::OBra(3));
+/// assert_eq!(tokens[4], Token::CBra(7));
+/// ```
+pub fn rebase(tokens: &mut [Token], offset: usize) {
+    for token in tokens {
+        let position = token.position_mut();
+        *position = position.saturating_add(offset);
+    }
+}
+
+/// Tokenizes the provided content and appends the resulting tokens to `tokens`.
+///
+/// The positions of the appended tokens are shifted by `offset` (see [rebase]), so that
+/// several fragments can be tokenized into a single, combined token stream.
+///
+/// # Errors
+///
+/// Returns the same errors as [tokenize]. On error, `tokens` is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::tokenizer::{tokenize, tokenize_into};
+///
+/// let mut tokens = tokenize("A{1,2}").unwrap();
+/// tokenize_into("B{3,4}", &mut tokens, 6).unwrap();
+/// assert_eq!(tokens, tokenize("A{1,2}B{3,4}").unwrap());
+/// ```
+pub fn tokenize_into(
+    content: &str,
+    tokens: &mut Vec<Token>,
+    offset: usize,
+) -> Result<(), TokenizationError> {
+    let mut fragment = tokenize(content)?;
+    rebase(&mut fragment, offset);
+    tokens.extend(fragment);
+    Ok(())
+}
+
+/// The category of a [SemanticToken], stable across the internal [Token] variants.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SemanticKind {
+    /// An opening or a closing brace.
+    Bracket,
+    /// A comma separating the items of a collection, or a pipe separating alternatives.
+    Separator,
+    /// A range operator, `..` or `..<`.
+    RangeOperator,
+    /// Literal text, including its escapes.
+    Literal,
+    /// A number, e.g. a range limit.
+    Number,
+}
+
+/// A span of the content and its category, see [semantic_tokens].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SemanticToken {
+    /// Byte range of the token in the content.
+    pub range: std::ops::Range<usize>,
+    /// Category of the token.
+    pub kind: SemanticKind,
+}
+
+/// Tokenizes the provided content like [tokenize], and returns the byte range and the
+/// [SemanticKind] of every token, in source order. Meant for syntax highlighting in editors.
+///
+/// A literal spans the escapes in it, e.g. `a\,b` is a single literal, 4 bytes long.
+///
+/// # Errors
+///
+/// Returns the same errors as [tokenize].
+///
+/// # Examples
+///
+/// ```
+/// use bracoxide::tokenizer::{semantic_tokens, SemanticKind};
+///
+/// let tokens = semantic_tokens("x{1..3}").unwrap();
+/// assert_eq!(tokens[0].kind, SemanticKind::Literal);
+/// assert_eq!(tokens[3].range, 3..5);
+/// assert_eq!(tokens[3].kind, SemanticKind::RangeOperator);
+/// ```
+pub fn semantic_tokens(content: &str) -> Result<Vec<SemanticToken>, TokenizationError> {
+    let tokens = tokenize(content)?;
+    // byte offset of every char, and of the end of the content.
+    let offsets: Vec<usize> = content
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain([content.len()])
+        .collect();
+    let ends = tokens
+        .iter()
+        .skip(1)
+        .map(Token::position)
+        .chain([offsets.len() - 1]);
+    Ok(tokens
+        .iter()
+        .zip(ends)
+        .map(|(token, next)| {
+            let start = token.position();
+            let (end, kind) = match token {
+                Token::OBra(_) | Token::CBra(_) => (start + token.width(), SemanticKind::Bracket),
+                Token::Comma(_) | Token::Pipe(_) => {
+                    (start + token.width(), SemanticKind::Separator)
+                }
+                Token::Range(_) | Token::ExclusiveRange(_) => {
+                    (start + token.width(), SemanticKind::RangeOperator)
+                }
+                Token::Number(..) => (start + token.width(), SemanticKind::Number),
+                // text runs up to the next token, over the escapes and quotes dropped from it.
+                Token::Text(..) => (next, SemanticKind::Literal),
+            };
+            SemanticToken {
+                range: offsets[start]..offsets[end],
+                kind,
+            }
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,5 +983,411 @@ mod tests {
                 Token::CBra(8),
             ])
         );
+        // a lone dot is text, wherever it is in the braces.
+        for (content, text) in [("{a.b}", "a.b"), ("{.a}", ".a"), ("{a.}", "a.")] {
+            assert_eq!(
+                tokenize(content),
+                Ok(vec![
+                    Token::OBra(0),
+                    Token::Text(Arc::new(text.to_owned()), 1),
+                    Token::CBra(text.len() + 1),
+                ]),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tokenize_into() {
+        let mut tokens = tokenize("A{1,2}").unwrap();
+        assert_eq!(tokenize_into("B{3,4}", &mut tokens, 6), Ok(()));
+        assert_eq!(tokens, tokenize("A{1,2}B{3,4}").unwrap());
+        assert_eq!(
+            tokenize_into("C", &mut tokens, 13),
+            Err(TokenizationError::NoBraces)
+        );
+        assert_eq!(tokens, tokenize("A{1,2}B{3,4}").unwrap());
+    }
+
+    #[test]
+    fn test_base() {
+        let document = "echo pre{a,1..3}post 'x";
+        let options = TokenizerOptions::default().base(5);
+        let mut expected = tokenize(&document[5..20]).unwrap();
+        rebase(&mut expected, 5);
+        let tokens = tokenize_with(&document[5..20], &options).unwrap();
+        assert_eq!(tokens, expected);
+        assert_eq!(tokens[1], Token::OBra(8));
+        assert_eq!(
+            tokens.last(),
+            Some(&Token::Text("post".to_owned().into(), 16))
+        );
+        let node = crate::parser::parse(&tokens).unwrap();
+        assert_eq!(node.span(), 5..20);
+        assert_eq!(
+            tokenize_with(&document[5..], &options.quotes(true)),
+            Err(TokenizationError::UnclosedQuote(21))
+        );
+        // positions past usize::MAX saturate rather than overflow.
+        let options = TokenizerOptions::default().base(usize::MAX - 1);
+        let tokens = tokenize_with("{a,b}", &options).unwrap();
+        assert_eq!(tokens[0], Token::OBra(usize::MAX - 1));
+        assert_eq!(tokens[4], Token::CBra(usize::MAX));
+        let node = crate::parser::parse(&tokens).unwrap();
+        assert_eq!(node.span(), usize::MAX - 1..usize::MAX);
+        assert_eq!(
+            crate::expand(&node),
+            Ok(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            tokenize_with("{a,b}'", &options.quotes(true)),
+            Err(TokenizationError::UnclosedQuote(usize::MAX))
+        );
+    }
+
+    #[test]
+    fn test_literal_backslash() {
+        let options = TokenizerOptions::default().literal_backslash(true);
+        assert_eq!(
+            tokenize_with("C:\\a\\{b,c}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("C:\\a\\".to_owned()), 0),
+                Token::OBra(5),
+                Token::Text(Arc::new("b".to_owned()), 6),
+                Token::Comma(7),
+                Token::Text(Arc::new("c".to_owned()), 8),
+                Token::CBra(9),
+            ])
+        );
+        // the braces are quoted rather than escaped.
+        let options = options.quotes(true);
+        assert_eq!(
+            tokenize_with("\\'{'\\{b,c}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("\\{\\".to_owned()), 0),
+                Token::OBra(5),
+                Token::Text(Arc::new("b".to_owned()), 6),
+                Token::Comma(7),
+                Token::Text(Arc::new("c".to_owned()), 8),
+                Token::CBra(9),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_verbatim_groups() {
+        let options = TokenizerOptions::default().verbatim_groups(true);
+        assert_eq!(
+            tokenize_with("x%{a,{b,c}}{d,e}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("x{a,{b,c}}".to_owned()), 0),
+                Token::OBra(11),
+                Token::Text(Arc::new("d".to_owned()), 12),
+                Token::Comma(13),
+                Token::Text(Arc::new("e".to_owned()), 14),
+                Token::CBra(15),
+            ])
+        );
+        assert_eq!(
+            tokenize_with("%{a\\}b}", &options),
+            Ok(vec![Token::Text(Arc::new("{a\\}b}".to_owned()), 0)])
+        );
+        // escaped or disabled, the marker is text.
+        assert_eq!(
+            tokenize_with("\\%{a}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("%".to_owned()), 0),
+                Token::OBra(2),
+                Token::Text(Arc::new("a".to_owned()), 3),
+                Token::CBra(4),
+            ])
+        );
+        assert_eq!(
+            tokenize("%{a}"),
+            Ok(vec![
+                Token::Text(Arc::new("%".to_owned()), 0),
+                Token::OBra(1),
+                Token::Text(Arc::new("a".to_owned()), 2),
+                Token::CBra(3),
+            ])
+        );
+        assert_eq!(
+            tokenize_with("%{a,b", &options),
+            Err(TokenizationError::NoBraces)
+        );
+    }
+
+    #[test]
+    fn test_max_tokens() {
+        let options = TokenizerOptions::default().max_tokens(5);
+        assert_eq!(tokenize_with("{a,b}", &options), tokenize("{a,b}"));
+        assert_eq!(
+            tokenize_with("{a,b}c", &options),
+            Err(TokenizationError::TooManyTokens(5))
+        );
+        // rejected after a few commas, before the missing closing brace is noticed at the end.
+        let content = format!("{{{}", ",".repeat(1 << 20));
+        assert_eq!(
+            tokenize_with(&content, &options),
+            Err(TokenizationError::TooManyTokens(5))
+        );
+    }
+
+    #[test]
+    fn test_tokenize_as_ref() {
+        let expected = tokenize("{a,b}");
+        let content = String::from("{a,b}");
+        assert_eq!(tokenize(&content), expected);
+        assert_eq!(tokenize(&std::borrow::Cow::Borrowed("{a,b}")), expected);
+        assert_eq!(tokenize(&Box::<str>::from("{a,b}")), expected);
+        fn generic<S: AsRef<str>>(content: S) -> Result<Vec<Token>, TokenizationError> {
+            tokenize(&content)
+        }
+        assert_eq!(generic(content), expected);
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        use SemanticKind::*;
+        let content = "é\\{x{1..<3,b\\,c}";
+        let tokens = semantic_tokens(content).unwrap();
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|t| (&content[t.range.clone()], t.kind))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("é\\{x", Literal),
+                ("{", Bracket),
+                ("1", Number),
+                ("..<", RangeOperator),
+                ("3", Number),
+                (",", Separator),
+                ("b\\,c", Literal),
+                ("}", Bracket),
+            ]
+        );
+        assert_eq!(tokens[0].range, 0..5);
+        assert_eq!(semantic_tokens("a"), Err(TokenizationError::NoBraces));
+    }
+
+    #[test]
+    fn test_render() {
+        let render = |content: &str| -> String {
+            tokenize(content)
+                .unwrap()
+                .iter()
+                .map(Token::render)
+                .collect()
+        };
+        assert_eq!(render("A{B,C{1..3}}D"), "A{B,C{1..3}}D");
+        assert_eq!(render("{a..<e}"), "{a..<e}");
+        assert_eq!(render("\\,{a,b}"), ",{a,b}");
+    }
+
+    #[test]
+    fn test_token_traits() {
+        let tokens = tokenize("{a,b}{a,c}").unwrap();
+        let unique: std::collections::HashSet<&Token> = tokens.iter().collect();
+        // every token has its own position, so none of them are equal.
+        assert_eq!(unique.len(), tokens.len());
+        let mut sorted = tokens.clone();
+        sorted.sort();
+        assert_eq!(sorted[..2], [Token::OBra(0), Token::OBra(5)]);
+        assert_eq!(
+            sorted[sorted.len() - 1],
+            Token::Text(Arc::new("c".into()), 8)
+        );
+    }
+
+    #[test]
+    fn test_empty_braces() {
+        assert_eq!(
+            tokenize("a{}"),
+            Ok(vec![
+                Token::Text(Arc::new("a".into()), 0),
+                Token::OBra(1),
+                Token::CBra(2)
+            ])
+        );
+        let options = TokenizerOptions::default().empty_braces(true);
+        assert_eq!(
+            tokenize_with("a{}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("a".into()), 0),
+                Token::OBra(1),
+                Token::Text(Arc::new(String::new()), 2),
+                Token::CBra(2)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_combined_options() {
+        for content in ["a{}", "'{a,b}'", "x\\{{1..3,\"b\"}"] {
+            assert_eq!(
+                tokenize_with(content, &TokenizerOptions::default()),
+                tokenize(content)
+            );
+        }
+        let options = TokenizerOptions::default().quotes(true).empty_braces(true);
+        assert_eq!(
+            tokenize_with("'a,b'{}", &options),
+            Ok(vec![
+                Token::Text(Arc::new("a,b".into()), 0),
+                Token::OBra(5),
+                Token::Text(Arc::new(String::new()), 6),
+                Token::CBra(6)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_alternation() {
+        assert_eq!(
+            tokenize("{a|b}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Text(Arc::new("a|b".into()), 1),
+                Token::CBra(4)
+            ])
+        );
+        let options = TokenizerOptions::default().alternation(true);
+        assert_eq!(
+            tokenize_with("{a|\\|b}", &options),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Text(Arc::new("a".into()), 1),
+                Token::Pipe(2),
+                Token::Text(Arc::new("|b".into()), 3),
+                Token::CBra(6)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_exclusive_range() {
+        assert_eq!(
+            tokenize("{1..<5}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Number(Arc::new("1".to_owned()), 1),
+                Token::ExclusiveRange(2),
+                Token::Number(Arc::new("5".to_owned()), 5),
+                Token::CBra(6),
+            ])
+        );
+        assert_eq!(
+            tokenize("{a..<}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Text(Arc::new("a".to_owned()), 1),
+                Token::ExclusiveRange(2),
+                Token::CBra(5),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        assert_eq!(
+            tokenize("a{b,\\\nc}"),
+            Ok(vec![
+                Token::Text(Arc::new("a".to_owned()), 0),
+                Token::OBra(1),
+                Token::Text(Arc::new("b".to_owned()), 2),
+                Token::Comma(3),
+                Token::Text(Arc::new("c".to_owned()), 6),
+                Token::CBra(7),
+            ])
+        );
+        assert_eq!(
+            tokenize("a{b,\\\r\nc}"),
+            Ok(vec![
+                Token::Text(Arc::new("a".to_owned()), 0),
+                Token::OBra(1),
+                Token::Text(Arc::new("b".to_owned()), 2),
+                Token::Comma(3),
+                Token::Text(Arc::new("c".to_owned()), 7),
+                Token::CBra(8),
+            ])
+        );
+        // each part of a split number keeps its position.
+        assert_eq!(
+            tokenize("{1\\\n2..3}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Number(Arc::new("1".to_owned()), 1),
+                Token::Number(Arc::new("2".to_owned()), 4),
+                Token::Range(5),
+                Token::Number(Arc::new("3".to_owned()), 7),
+                Token::CBra(8),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_quotes() {
+        let options = TokenizerOptions::default().quotes(true);
+        assert_eq!(
+            tokenize_with("'{a,b}'", &options),
+            Err(TokenizationError::NoBraces)
+        );
+        assert_eq!(
+            tokenize_with("\"{a,b}\"", &options),
+            Ok(vec![
+                Token::OBra(1),
+                Token::Text(Arc::new("a".to_owned()), 2),
+                Token::Comma(3),
+                Token::Text(Arc::new("b".to_owned()), 4),
+                Token::CBra(5),
+            ])
+        );
+        assert_eq!(
+            tokenize_with("{a,b}'c", &options),
+            Err(TokenizationError::UnclosedQuote(5))
+        );
+        // quotes are ordinary text by default.
+        assert_eq!(
+            tokenize("'{a}'"),
+            Ok(vec![
+                Token::Text(Arc::new("'".to_owned()), 0),
+                Token::OBra(1),
+                Token::Text(Arc::new("a".to_owned()), 2),
+                Token::CBra(3),
+                Token::Text(Arc::new("'".to_owned()), 4),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_digit_groups() {
+        assert_eq!(
+            tokenize("{1_000..1_002}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Number(Arc::new("1_000".to_owned()), 1),
+                Token::Range(6),
+                Token::Number(Arc::new("1_002".to_owned()), 8),
+                Token::CBra(13),
+            ])
+        );
+        // only an underscore between digits groups them.
+        assert_eq!(
+            tokenize("{1_,_2,a_1}"),
+            Ok(vec![
+                Token::OBra(0),
+                Token::Number(Arc::new("1".to_owned()), 1),
+                Token::Text(Arc::new("_".to_owned()), 2),
+                Token::Comma(3),
+                Token::Text(Arc::new("_".to_owned()), 4),
+                Token::Number(Arc::new("2".to_owned()), 5),
+                Token::Comma(6),
+                Token::Text(Arc::new("a_".to_owned()), 7),
+                Token::Number(Arc::new("1".to_owned()), 9),
+                Token::CBra(10),
+            ])
+        );
     }
 }